@@ -1,9 +1,9 @@
-use std::{collections::{self, HashMap}};
+use std::{collections::{self, HashMap}, fmt};
 
-use proc_macro2::{TokenStream, Ident};
+use proc_macro2::Ident;
 use serde_dhall::SimpleType;
 
-use crate::{META_ENV_POSTFIX, META_ENV_PREFIX, META_FIELD_POSTFIX, META_FIELD_PREFIX_CONTENT, META_FIELD_PREFIX_NAME, util::{create_error, IteratorSingle}};
+use crate::{META_ENV_CATCH_ALL, META_ENV_POINTER, META_ENV_POSTFIX, META_ENV_PREFIX, META_ENV_RECURSIVE, META_FIELD_CATCH_ALL, META_FIELD_PHANTOM, META_FIELD_POINTER_CONTENT, META_FIELD_POINTER_KIND, META_FIELD_POSTFIX, META_FIELD_PREFIX_CONTENT, META_FIELD_PREFIX_NAME, META_FIELD_RECURSIVE, options::Pointer, util::{Error, IteratorSingle}};
 
 /// A wrapper for `SimpleType::Union` and `SimpleType::Record` that hides the modifications made to the raw Dhall for supporting metadata (e.g. type names)
 #[derive(Debug, Copy, Clone)]
@@ -18,11 +18,11 @@ impl<'a> AggregateType<'a> {
         Self(AggregateTypeInternal::Union(u))
     }
 
-    pub fn try_new(st: &'a SimpleType) -> Result<Self, TokenStream> {
+    pub fn try_new(st: &'a SimpleType) -> Result<Self, Error> {
         AggregateTypeInternal::try_new(st).map(Self)
     }
 
-    pub fn info(self) -> Result<AggregateTypeInfo<'a>, TokenStream> {
+    pub fn info(self) -> Result<AggregateTypeInfo<'a>, Error> {
         self.0.info()
     }
 
@@ -34,60 +34,221 @@ impl<'a> AggregateType<'a> {
         self.0.is_unit()
     }
     
+    /// The Dhall source which gives this type the name `name` using metadata, as it would be written in place of the type itself
+    pub fn with_name_metadata_example(self, name: &str) -> String {
+        match self.0 {
+            AggregateTypeInternal::Record(_) => format!("{} //\\\\ (env:{} <{}> ? {{}})", self, META_ENV_POSTFIX, name),
+            AggregateTypeInternal::Union(_) => format!("(env:{} ? (\\(T: Type) -> \\(T: Type) -> T)) <{}> {}", META_ENV_PREFIX, name, self),
+        }
+    }
+
+    /// Check that this type is `other`, or an instantiation of it if `other` is generic, returning the type parameter
     pub fn is_form_of(self, other: AggregateType) -> Result<Option<&'a SimpleType>, ()> {
-        fn is_eq_all<'a>(a: AggregateType<'a>, b: AggregateType) -> Result<Option<&'a SimpleType>, ()> {
-            let ai = a.0.info().map_err(|_| ())?;
-            let bi = b.0.info().map_err(|_| ())?;
-
-            if ai.name_override() == bi.name_override()
-            && ai.kind() == bi.kind() {
-                let mut parameter: Option<&'a SimpleType> = None;
-                for (ak, av) in ai.iter_members().map_err(|_| ())? {
-                    let bv = bi.get_member(ak)?;
-                    let result = match (av, bv) {
-                        // Union alternatives with associated types or record values
-                        (Some(av), Some(bv)) => is_eq(av, bv),
-                        // Union alternatives with no associated type
-                        (None, None) =>  Ok(None),
-                        // Union alternatives with only one having no associated type
-                        _ => Err(()),
-                    }?;
-
-                    match (parameter, result) {
-                        (Some(p), Some(r)) => is_eq(p, r).map(|_| ())?,
-                        (None, Some(_)) => parameter = result,
-                        _ => { }
-                    }
-                }
+        is_form_of_all(self, other)
+    }
+}
 
-                Ok(parameter)
-            } else {
-                Err(())
+fn is_form_of_all<'a>(a: AggregateType<'a>, b: AggregateType) -> Result<Option<&'a SimpleType>, ()> {
+    let ai = a.0.info().map_err(|_| ())?;
+    let bi = b.0.info().map_err(|_| ())?;
+
+    if ai.name_override() == bi.name_override()
+    && ai.kind() == bi.kind() {
+        let mut parameter: Option<&'a SimpleType> = None;
+        // A type parameter not used by any member is only known if it was marked with `env:rust_phantom`, otherwise it's left undetermined
+        match (ai.phantom_parameter(), bi.phantom_parameter()) {
+            (Some(ap), Some(bp)) => parameter = simple_type_is_form_of(ap, bp)?,
+            (Some(_), None) => return Err(()),
+            (None, _) => { },
+        }
+        for (ak, av) in ai.iter_members().map_err(|_| ())? {
+            let bv = bi.get_member(ak)?;
+            let result = match (av, bv) {
+                // Union alternatives with associated types or record values
+                (Some(av), Some(bv)) => simple_type_is_form_of(av, bv),
+                // Union alternatives with no associated type
+                (None, None) =>  Ok(None),
+                // Union alternatives with only one having no associated type
+                _ => Err(()),
+            }?;
+
+            match (parameter, result) {
+                (Some(p), Some(r)) => simple_type_is_form_of(p, r).map(|_| ())?,
+                (None, Some(_)) => parameter = result,
+                _ => { }
             }
         }
 
-        fn is_eq<'a>(a: &'a SimpleType, b: &SimpleType) -> Result<Option<&'a SimpleType>, ()> {
-            if AggregateTypeInternal::try_new(b).map(AggregateTypeInternal::is_generic_sentinel).unwrap_or(false) {
-                return Ok(Some(a));
-            }
+        Ok(parameter)
+    } else {
+        Err(())
+    }
+}
 
-            match (a, b) {
-                (SimpleType::Bool, SimpleType::Bool)
-              | (SimpleType::Natural, SimpleType::Natural)
-              | (SimpleType::Integer, SimpleType::Integer)
-              | (SimpleType::Double, SimpleType::Double)
-              | (SimpleType::Text, SimpleType::Text) => Ok(None),
+/// Check that `a` is `b`, or an instantiation of it if `b` is generic, returning the type parameter
+pub fn simple_type_is_form_of<'a>(a: &'a SimpleType, b: &SimpleType) -> Result<Option<&'a SimpleType>, ()> {
+    if AggregateTypeInternal::try_new(b).map(AggregateTypeInternal::is_generic_sentinel).unwrap_or(false) {
+        return Ok(Some(a));
+    }
 
-                (SimpleType::Optional(a), SimpleType::Optional(b))
-              | (SimpleType::List(a), SimpleType::List(b)) => is_eq(a, b),
+    // A recursive reference stands in for any form of the type it names, which is how depth limited unrollings of a recursive type match
+    match (recursive_metadata(a), recursive_metadata(b)) {
+        (Ok(Some(a_name)), Ok(Some(b_name))) => return if a_name == b_name { Ok(None) } else { Err(()) },
+        (Ok(Some(_)), Ok(None)) | (Ok(None), Ok(Some(_))) => return match (a, b) {
+            (SimpleType::Record(_) | SimpleType::Union(_), SimpleType::Record(_) | SimpleType::Union(_)) => Ok(None),
+            _ => Err(()),
+        },
+        (Ok(None), Ok(None)) => { },
+        _ => return Err(()),
+    }
 
-                (SimpleType::Record(a), SimpleType::Record(b)) => is_eq_all(AggregateType(AggregateTypeInternal::Record(a)), AggregateType(AggregateTypeInternal::Record(b))),
-                (SimpleType::Union(a), SimpleType::Union(b)) => is_eq_all(AggregateType(AggregateTypeInternal::Union(a)), AggregateType(AggregateTypeInternal::Union(b))),
-                _ => Err(())
-            }
+    match (pointer_metadata(a), pointer_metadata(b)) {
+        (Ok(Some((a_pointer, a))), Ok(Some((b_pointer, b)))) if a_pointer == b_pointer => return simple_type_is_form_of(a, b),
+        (Ok(None), Ok(None)) => { },
+        _ => return Err(()),
+    }
+
+    match (alias_metadata(a), alias_metadata(b)) {
+        (Ok(Some((a_name, a))), Ok(Some((b_name, b)))) if a_name == b_name => return simple_type_is_form_of(a, b),
+        (Ok(None), Ok(None)) => { },
+        _ => return Err(()),
+    }
+
+    match (a, b) {
+        (SimpleType::Bool, SimpleType::Bool)
+      | (SimpleType::Natural, SimpleType::Natural)
+      | (SimpleType::Integer, SimpleType::Integer)
+      | (SimpleType::Double, SimpleType::Double)
+      | (SimpleType::Text, SimpleType::Text) => Ok(None),
+
+        (SimpleType::Optional(a), SimpleType::Optional(b))
+      | (SimpleType::List(a), SimpleType::List(b)) => simple_type_is_form_of(a, b),
+
+        (SimpleType::Record(a), SimpleType::Record(b)) => is_form_of_all(AggregateType(AggregateTypeInternal::Record(a)), AggregateType(AggregateTypeInternal::Record(b))),
+        (SimpleType::Union(a), SimpleType::Union(b)) => is_form_of_all(AggregateType(AggregateTypeInternal::Union(a)), AggregateType(AggregateTypeInternal::Union(b))),
+        _ => Err(())
+    }
+}
+
+/// Whether `st` contains the type parameter of a generic type, outside of other named generic types
+pub fn simple_type_is_open_generic(st: &SimpleType) -> bool {
+    match st {
+        SimpleType::Optional(st) | SimpleType::List(st) => simple_type_is_open_generic(st),
+        SimpleType::Record(_) | SimpleType::Union(_) => match metadata_content(st) {
+            Some(content) => simple_type_is_open_generic(content),
+            _ => AggregateTypeInternal::try_new(st).ok()
+                    .and_then(|at| at.info().ok())
+                    .is_some_and(|ati| ati.is_open_generic()),
+        },
+        _ => false,
+    }
+}
+
+/// Whether `st` contains a reference to a recursive type, see `META_ENV_RECURSIVE`
+pub fn simple_type_is_recursive(st: &SimpleType) -> bool {
+    match st {
+        SimpleType::Optional(st) | SimpleType::List(st) => simple_type_is_recursive(st),
+        SimpleType::Record(_) | SimpleType::Union(_) => match (recursive_metadata(st), metadata_content(st)) {
+            (Ok(Some(_)), _) => true,
+            (_, Some(content)) => simple_type_is_recursive(content),
+            _ => AggregateTypeInternal::try_new(st).ok()
+                    .and_then(|at| at.info().ok())
+                    .is_some_and(|ati| ati.is_recursive()),
+        },
+        _ => false,
+    }
+}
+
+/// The name of the type referenced by a `env:rust_recursive` placeholder (e.g. `rust_recursive <Tree>`)
+pub fn recursive_metadata(st: &SimpleType) -> Result<Option<&str>, Error> {
+    match st {
+        SimpleType::Record(r) => match r.get(META_FIELD_RECURSIVE) {
+            Some(name_ty) if r.len() == 1 => AggregateTypeInternal::name_from_metadata(name_ty, META_ENV_RECURSIVE)
+                .map(Some)
+                .map_err(AggregateTypeInternal::meta_error),
+            Some(_) => Err(AggregateTypeInternal::meta_error((META_ENV_RECURSIVE, MetaError::InvalidForm))),
+            None => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The pointer and content of a type wrapped with `env:rust_pointer` (e.g. `rust_pointer <Arc> Config`)
+pub fn pointer_metadata(st: &SimpleType) -> Result<Option<(Pointer, &SimpleType)>, Error> {
+    let r = match st {
+        SimpleType::Record(r) => r,
+        _ => return Ok(None),
+    };
+    match (r.get(META_FIELD_POINTER_KIND), r.get(META_FIELD_POINTER_CONTENT)) {
+        (Some(pointer_ty), Some(content)) => {
+            let pointer = AggregateTypeInternal::name_from_metadata(pointer_ty, META_ENV_POINTER).map_err(AggregateTypeInternal::meta_error)?;
+            let pointer = pointer.parse().map_err(|_| Error::new(format!("The pointer passed to env:{} must be one of <Box>, <Rc> or <Arc>", META_ENV_POINTER)))?;
+            Ok(Some((pointer, content)))
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The content of a type alias or pointer given with metadata, which has the same Dhall type as the metadata
+pub fn metadata_content(st: &SimpleType) -> Option<&SimpleType> {
+    match (alias_metadata(st), pointer_metadata(st)) {
+        (Ok(Some((_, content))), _) | (_, Ok(Some((_, content)))) => Some(content),
+        _ => None,
+    }
+}
+
+/// The name and content of a type that is not a record or union, named with `env:rust_type` (e.g. `rust_type <UserId> Natural`)
+pub fn alias_metadata(st: &SimpleType) -> Result<Option<(&str, &SimpleType)>, Error> {
+    let r = match st {
+        SimpleType::Record(r) => r,
+        _ => return Ok(None),
+    };
+    match (r.get(META_FIELD_PREFIX_NAME), r.get(META_FIELD_PREFIX_CONTENT)) {
+        (Some(_), Some(SimpleType::Record(_) | SimpleType::Union(_))) => Ok(None),
+        (Some(name_ty), Some(content)) => AggregateTypeInternal::name_from_metadata(name_ty, META_ENV_PREFIX)
+            .map(|name| Some((name, content)))
+            .map_err(AggregateTypeInternal::meta_error),
+        _ => Ok(None),
+    }
+}
+
+impl<'a> fmt::Display for AggregateType<'a> {
+    /// Display the type in Dhall syntax, without metadata
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_dhall(f)
+    }
+}
+
+/// Displays a `SimpleType` in Dhall syntax, hiding metadata, showing the generic sentinel as `T` and recursive references by name
+pub struct DhallTypeDisplay<'a>(pub &'a SimpleType);
+
+impl<'a> DhallTypeDisplay<'a> {
+    fn fmt_nested(st: &SimpleType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let st = metadata_content(st).unwrap_or(st);
+        match st {
+            SimpleType::Optional(_) | SimpleType::List(_) => write!(f, "({})", DhallTypeDisplay(st)),
+            _ => fmt::Display::fmt(&DhallTypeDisplay(st), f),
         }
+    }
+}
 
-        is_eq_all(self, other)
+impl<'a> fmt::Display for DhallTypeDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            SimpleType::Bool => f.write_str("Bool"),
+            SimpleType::Natural => f.write_str("Natural"),
+            SimpleType::Integer => f.write_str("Integer"),
+            SimpleType::Double => f.write_str("Double"),
+            SimpleType::Text => f.write_str("Text"),
+            SimpleType::Optional(o) => { f.write_str("Optional ")?; Self::fmt_nested(o, f) }
+            SimpleType::List(l) => { f.write_str("List ")?; Self::fmt_nested(l, f) }
+            SimpleType::Record(r) => match (recursive_metadata(self.0), metadata_content(self.0)) {
+                (Ok(Some(name)), _) => f.write_str(name),
+                (_, Some(content)) => fmt::Display::fmt(&DhallTypeDisplay(content), f),
+                _ => AggregateTypeInternal::Record(r).fmt_dhall(f),
+            },
+            SimpleType::Union(u) => AggregateTypeInternal::Union(u).fmt_dhall(f),
+        }
     }
 }
 
@@ -98,31 +259,64 @@ enum AggregateTypeInternal<'a> {
 }
 
 impl<'a> AggregateTypeInternal<'a> {
-    pub fn try_new(st: &'a SimpleType) -> Result<Self, TokenStream> {
+    pub fn try_new(st: &'a SimpleType) -> Result<Self, Error> {
         match st {
             SimpleType::Record(r) => Ok(Self::Record(r)),
             SimpleType::Union(u) => Ok(Self::Union(u)),
-            _ => Err(create_error("SimpleType is not an aggregate type"))
+            _ => Err(Error::new("SimpleType is not an aggregate type"))
         }
     }
 
-    pub fn info(self) -> Result<AggregateTypeInfo<'a>, TokenStream> {
-        self.info_internal().map_err(|(env_name, e)| {
-            let error_msg = match e {
-                MetaError::IdentNotUnion => format!("The type passed to env:{} must be a single alternative union", env_name),
-                MetaError::IdentNotSingleAlternative => format!("The union passed to env:{} must have a single alternative", env_name),
-                MetaError::NotAggregateType => format!("The type marked by env:{} must be a record or a union", env_name),
-                MetaError::InvalidOverrideIdent => format!("The name specified for env:{} is not a valid Rust identifier", env_name),
-                MetaError::InvalidForm => format!("env:{} is used incorrectly, or there are conflicting members", env_name),
-            };
-            
-            create_error(&error_msg)
-        })
+    pub fn info(self) -> Result<AggregateTypeInfo<'a>, Error> {
+        self.info_internal().map_err(Self::meta_error)
+    }
+
+    fn meta_error((env_name, e): (&'static str, MetaError)) -> Error {
+        let error_msg = match e {
+            MetaError::IdentNotUnion => format!("The type passed to env:{} must be a single alternative union", env_name),
+            MetaError::IdentNotSingleAlternative => format!("The union passed to env:{} must have a single alternative", env_name),
+            MetaError::NotAggregateType => format!("The type marked by env:{} must be a record or a union", env_name),
+            MetaError::NotUnion => format!("The type marked by env:{} must be a union", env_name),
+            MetaError::InvalidOverrideIdent => format!("The name specified for env:{} is not a valid Rust identifier", env_name),
+            MetaError::InvalidForm => format!("env:{} is used incorrectly, or there are conflicting members", env_name),
+        };
+
+        Error::new(error_msg)
+    }
+
+    /// The name given by the single alternative union `name_ty` passed to `env_name`
+    fn name_from_metadata<'n>(name_ty: &'n SimpleType, env_name: &'static str) -> Result<&'n str, (&'static str, MetaError)> {
+        if let SimpleType::Union(u) = name_ty {
+            match u.iter().single() {
+                Ok((variant_name, _variant_type)) =>
+                    // Ensure the name override will be a valid identifier
+                    if syn::parse_str::<Ident>(variant_name.as_str()).is_ok() {
+                        Ok(variant_name.as_str())
+                    } else {
+                        Err((env_name, MetaError::InvalidOverrideIdent))
+                    }
+                Err(_) => Err((env_name, MetaError::IdentNotSingleAlternative))
+            }
+        } else {
+            Err((env_name, MetaError::IdentNotUnion))
+        }
     }
 
     fn info_internal(self) -> Result<AggregateTypeInfo<'a>, (&'static str, MetaError)> {
         // This isn't perfectly robust, some abomination of nested meta stuff could confuse us, but it would have to be intentional
         match self {
+            // Check for the catch-all function (env:rust_catch_all ? (\(T: Type)->T) < ... >), which may wrap a named union
+            Self::Record(r) if r.contains_key(META_FIELD_CATCH_ALL) => {
+                let content = match r.get(META_FIELD_CATCH_ALL) {
+                    Some(content) if r.len() == 1 => content,
+                    _ => return Err((META_ENV_CATCH_ALL, MetaError::InvalidForm)),
+                };
+                let info = Self::try_new(content).map_err(|_| (META_ENV_CATCH_ALL, MetaError::NotUnion))?.info_internal()?;
+                match info.kind() {
+                    AggregateKind::Union => Ok(AggregateTypeInfo { has_catch_all: true, ..info }),
+                    AggregateKind::Record => Err((META_ENV_CATCH_ALL, MetaError::NotUnion)),
+                }
+            }
             Self::Record(r) => {
                 // Check for the the prefix function ($env:rust_type <MyName> ? (\(t: Type)->t) { ... }) fields
                 let result = match (r.get(META_FIELD_PREFIX_NAME), r.get(META_FIELD_PREFIX_CONTENT)) {
@@ -138,19 +332,11 @@ impl<'a> AggregateTypeInternal<'a> {
                 .or_else(|| r.get(META_FIELD_POSTFIX).map(|ty_name_union| (ty_name_union, self, META_ENV_POSTFIX)));
  
                 if let Some((name_ty, ty, env_name)) = result {
-                    if let SimpleType::Union(u) = name_ty {
-                        match u.iter().single() {
-                            Ok((variant_name, _variant_type)) => 
-                                // Ensure the name override will be a valid identifier
-                                if syn::parse_str::<Ident>(variant_name.as_str()).is_ok() {
-                                    Ok(AggregateTypeInfo::new(Some(variant_name.as_str()), ty))
-                                } else {
-                                    Err((env_name, MetaError::InvalidOverrideIdent))
-                                }
-                            Err(_) => Err((env_name, MetaError::IdentNotSingleAlternative))
-                        }
-                    } else {
-                        Err((env_name, MetaError::IdentNotUnion))
+                    let name = Self::name_from_metadata(name_ty, env_name)?;
+                    // A named union may be marked with env:rust_catch_all inside the name
+                    match ty {
+                        Self::Record(content) if content.contains_key(META_FIELD_CATCH_ALL) => ty.info_internal().map(|info| AggregateTypeInfo { name_override: Some(name), ..info }),
+                        _ => Ok(AggregateTypeInfo::new(Some(name), ty)),
                     }
                 } else {
                     Ok(AggregateTypeInfo::new(None, self))
@@ -174,6 +360,57 @@ impl<'a> AggregateTypeInternal<'a> {
             Self::Union(u) => u.iter().single() == Ok((&"__sentinel".to_string(), &None)),
         }
     }
+
+    fn fmt_dhall(self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_generic_sentinel() {
+            return f.write_str("T");
+        }
+
+        let info = match self.info() {
+            Ok(info) => info,
+            // Metadata is malformed, so show the type as-is
+            Err(_) => return match self {
+                Self::Record(r) => fmt::Display::fmt(&SimpleType::Record(r.clone()), f),
+                Self::Union(u) => fmt::Display::fmt(&SimpleType::Union(u.clone()), f),
+            },
+        };
+        let mut members: Vec<_> = match info.iter_members() {
+            Ok(members) => members.collect(),
+            Err(_) => return Err(fmt::Error),
+        };
+        members.sort_by_key(|(member_name, _)| *member_name);
+
+        match info.kind() {
+            AggregateKind::Record if members.is_empty() => f.write_str("{}"),
+            AggregateKind::Record => {
+                f.write_str("{ ")?;
+                for (index, (member_name, member_type)) in members.into_iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{} : ", member_name)?;
+                    if let Some(member_type) = member_type {
+                        fmt::Display::fmt(&DhallTypeDisplay(member_type), f)?;
+                    }
+                }
+                f.write_str(" }")
+            }
+            AggregateKind::Union if members.is_empty() => f.write_str("<>"),
+            AggregateKind::Union => {
+                f.write_str("< ")?;
+                for (index, (member_name, member_type)) in members.into_iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    f.write_str(member_name)?;
+                    if let Some(member_type) = member_type {
+                        write!(f, " : {}", DhallTypeDisplay(member_type))?;
+                    }
+                }
+                f.write_str(" >")
+            }
+        }
+    }
 }
 
 /// Computed information from an `AggregateType`
@@ -181,6 +418,7 @@ impl<'a> AggregateTypeInternal<'a> {
 pub struct AggregateTypeInfo<'a> {
     name_override: Option<&'a str>,
     inner_aggregate: AggregateTypeInternal<'a>,
+    has_catch_all: bool,
 }
 
 impl<'a> AggregateTypeInfo<'a> {
@@ -188,6 +426,7 @@ impl<'a> AggregateTypeInfo<'a> {
         Self {
             name_override,
             inner_aggregate,
+            has_catch_all: false,
         }
     }
 
@@ -195,6 +434,11 @@ impl<'a> AggregateTypeInfo<'a> {
         self.name_override
     }
 
+    /// Whether the union was marked with `env:rust_catch_all`, so its enum gets a catch-all variant
+    pub fn has_catch_all(&self) -> bool {
+        self.has_catch_all
+    }
+
     pub fn kind(&self) -> AggregateKind {
         match &self.inner_aggregate {
             AggregateTypeInternal::Record(_) => AggregateKind::Record,
@@ -202,7 +446,7 @@ impl<'a> AggregateTypeInfo<'a> {
         }
     }
 
-    pub fn iter_members(&self) -> Result<AggregateTypeMembers<'a>, TokenStream> {
+    pub fn iter_members(&self) -> Result<AggregateTypeMembers<'a>, Error> {
         Ok(match self.inner_aggregate {
             AggregateTypeInternal::Record(r) => AggregateTypeMembers::Record(AggregateTypeRecordFieldsIter(r.iter())),
             AggregateTypeInternal::Union(u) => AggregateTypeMembers::Union(AggregateTypeUnionAlternativesIter(u.iter())),
@@ -216,20 +460,36 @@ impl<'a> AggregateTypeInfo<'a> {
         }
     }
 
+    /// The type marking an otherwise unused type parameter, see `META_ENV_PHANTOM`
+    pub fn phantom_parameter(&self) -> Option<&'a SimpleType> {
+        match self.inner_aggregate {
+            AggregateTypeInternal::Record(r) => r.get(META_FIELD_PHANTOM),
+            AggregateTypeInternal::Union(_) => None,
+        }
+    }
+
     pub fn is_open_generic(&self) -> bool {
-        if self.inner_aggregate.is_generic_sentinel() {
+        if self.inner_aggregate.is_generic_sentinel() || self.phantom_parameter().is_some_and(simple_type_is_open_generic) {
             true
         } else {
             match self.iter_members() {
                 Ok(members) => members.into_iter()
                     .filter_map(|(_, st)| st)
-                    .filter_map(|st| AggregateTypeInternal::try_new(st).ok())
-                    .filter_map(|at| at.info().ok())
-                    .any(|ati| ati.is_open_generic()),
+                    .any(simple_type_is_open_generic),
                 Err(_) => false,
             }
         }
     }
+
+    /// Whether any member refers to a recursive type, directly or in nested types
+    pub fn is_recursive(&self) -> bool {
+        match self.iter_members() {
+            Ok(members) => members.into_iter()
+                .filter_map(|(_, st)| st)
+                .any(simple_type_is_recursive),
+            Err(_) => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -254,9 +514,9 @@ impl<'a> Iterator for AggregateTypeRecordFieldsIter<'a> {
     type Item = (&'a str, &'a SimpleType);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Don't expose the META_FIELD_POSTFIX name override field
+        // Don't expose the META_FIELD_POSTFIX name override field, or the META_FIELD_PHANTOM type parameter marker
         match self.0.next().map(|(k, v)| (k.as_str(), v)) {
-            Some((META_FIELD_POSTFIX, _)) => self.next(),
+            Some((META_FIELD_POSTFIX | META_FIELD_PHANTOM, _)) => self.next(),
             next => next,
         }
     }
@@ -283,6 +543,7 @@ enum MetaError {
     IdentNotUnion,
     IdentNotSingleAlternative,
     NotAggregateType,
+    NotUnion,
     InvalidOverrideIdent,
     InvalidForm,
 }
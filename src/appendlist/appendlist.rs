@@ -15,7 +15,7 @@ use super::common::{chunk_size, chunk_start, index_chunk};
 ///
 /// For example, this would be illegal with a `Vec`:
 ///
-/// ```
+/// ```ignore
 /// use appendlist::AppendList;
 ///
 /// let list = AppendList::new();
@@ -176,15 +176,15 @@ impl<T> AppendList<T> {
         let chunk_id = index_chunk(index);
         let chunk_start = chunk_start(chunk_id);
 
-        return Some(&self.chunks()[chunk_id][index - chunk_start]);
+        Some(&self.chunks()[chunk_id][index - chunk_start])
     }
 
     /// Get an iterator over the list
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         self.check_invariants();
 
         Iter {
-            list: &self,
+            list: self,
             index: 0,
         }
     }
@@ -406,8 +406,8 @@ mod test {
             assert_eq!(l.len(), i + 1);
         }
 
-        for i in 0..size {
-            assert_eq!(Some(&refs[i]), l.get(i));
+        for (i, r) in refs.iter().enumerate() {
+            assert_eq!(Some(r), l.get(i));
         }
     }
 }
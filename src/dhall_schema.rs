@@ -0,0 +1,229 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{ext::IdentExt, spanned::Spanned};
+
+use crate::hand_written::{is_skipped, rename_all_rule, serde_name, SerdeDirection};
+use crate::util::Error;
+
+const ATTRIBUTE_NAME: &str = "dhall_schema";
+
+/// Options set with `#[dhall_schema(...)]` on the type
+#[derive(Debug, Default)]
+struct SchemaOptions {
+    // Emit a `{ Type, default }` schema using the type's `Default` impl
+    default: bool,
+}
+
+fn parse_options(attrs: &[syn::Attribute]) -> Result<SchemaOptions, Error> {
+    let mut options = SchemaOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(ATTRIBUTE_NAME)) {
+        let nested = match attr.parse_meta().map_err(|e| Error::new_spanned(e.to_string(), e.span()))? {
+            syn::Meta::List(list) => list.nested,
+            meta => return Err(Error::new_spanned(format!("Expected '{}(...)'", ATTRIBUTE_NAME), meta.span())),
+        };
+        for meta in nested {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("default") => options.default = true,
+                meta => return Err(Error::new_spanned(format!("Unknown {} option", ATTRIBUTE_NAME), meta.span())),
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// The Dhall builtin a Rust type maps to, the inverse of `PrimitiveIdents`
+pub(crate) fn primitive_dhall_type(ident: &str) -> Option<&'static str> {
+    Some(match ident {
+        "bool" => "Bool",
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => "Natural",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => "Integer",
+        "f32" | "f64" => "Double",
+        "String" | "str" | "char" => "Text",
+        _ => return None,
+    })
+}
+
+pub(crate) fn generic_arguments(segment: &syn::PathSegment) -> Vec<&syn::Type> {
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => arguments.args.iter().filter_map(|argument| match argument {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Tokens evaluating to the Dhall text of `ty` as a `String`, and whether that text can be used as a function argument without parentheses
+fn tokenize_dhall_type(ty: &syn::Type, type_parameters: &[Ident]) -> Result<(TokenStream, bool), Error> {
+    let unsupported = || Error::new_spanned("Type has no Dhall equivalent", ty.span());
+    let argument = |ty: &syn::Type| -> Result<TokenStream, Error> {
+        let (tokens, is_atomic) = tokenize_dhall_type(ty, type_parameters)?;
+        Ok(if is_atomic { tokens } else { quote!(::std::format!("({})", #tokens)) })
+    };
+
+    match ty {
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => Ok((quote!(::std::string::String::from("{}")), true)),
+        syn::Type::Reference(reference) => tokenize_dhall_type(&reference.elem, type_parameters),
+        syn::Type::Paren(paren) => tokenize_dhall_type(&paren.elem, type_parameters),
+        syn::Type::Group(group) => tokenize_dhall_type(&group.elem, type_parameters),
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last().ok_or_else(unsupported)?;
+            let name = segment.ident.to_string();
+            let arguments = generic_arguments(segment);
+
+            if path.path.segments.len() == 1 && arguments.is_empty() && type_parameters.contains(&segment.ident) {
+                return Ok((quote!(::std::string::String::from(#name)), true));
+            }
+            if let Some(dhall_type) = primitive_dhall_type(&name).filter(|_| arguments.is_empty()) {
+                return Ok((quote!(::std::string::String::from(#dhall_type)), true));
+            }
+
+            match (name.as_str(), arguments.as_slice()) {
+                ("Option", [inner]) => {
+                    let inner = argument(inner)?;
+                    Ok((quote!(::std::format!("Optional {}", #inner)), false))
+                },
+                ("Vec" | "VecDeque" | "HashSet" | "BTreeSet", [inner]) => {
+                    let inner = argument(inner)?;
+                    Ok((quote!(::std::format!("List {}", #inner)), false))
+                },
+                ("Box" | "Rc" | "Arc", [inner]) => tokenize_dhall_type(inner, type_parameters),
+                // serde_dhall serializes maps to records, whose fields aren't known up front, and `dhall_types`
+                // generates a list of entries rather than a map from `toMap` style lists, so neither round-trips
+                ("HashMap" | "BTreeMap", _) => Err(Error::new_spanned("Maps have no Dhall type which reads back as the same map, use a `Vec` of key and value records instead", ty.span())),
+                // Any other type is expected to derive `DhallSchema` too, and is imported from its own file
+                (_, arguments) => {
+                    let arguments = arguments.iter().map(|ty| argument(ty)).collect::<Result<Vec<_>, Error>>()?;
+                    let is_atomic = arguments.is_empty();
+                    Ok((quote!({
+                        let mut dhall_type = <#ty>::dhall_type_import();
+                        #(
+                            dhall_type.push(' ');
+                            dhall_type.push_str(&#arguments);
+                        )*
+                        dhall_type
+                    }), is_atomic))
+                },
+            }
+        },
+        _ => Err(unsupported()),
+    }
+}
+
+/// Tokens evaluating to the Dhall record type of `fields` as a `String`, named as serde serializes them with the `rename_all` rule of their container
+fn tokenize_record(fields: &syn::Fields, rename_all: Option<&str>, type_parameters: &[Ident]) -> Result<TokenStream, Error> {
+    let fields = match fields {
+        syn::Fields::Named(fields) => &fields.named,
+        syn::Fields::Unit => return Ok(quote!(::std::string::String::from("{}"))),
+        syn::Fields::Unnamed(fields) => return Err(Error::new_spanned("Tuple structs have no Dhall equivalent, use named fields instead", fields.span())),
+    };
+    let fields = fields.iter().filter(|field| !is_skipped(&field.attrs, SerdeDirection::Serialize)).collect::<Vec<_>>();
+    if fields.is_empty() {
+        return Ok(quote!(::std::string::String::from("{}")));
+    }
+
+    let names = fields.iter().map(|field| serde_name(field.ident.as_ref().expect("Named fields have idents"), &field.attrs, rename_all, false, SerdeDirection::Serialize));
+    let types = fields.iter().map(|field| tokenize_dhall_type(&field.ty, type_parameters).map(|(tokens, _)| tokens)).collect::<Result<Vec<_>, Error>>()?;
+    Ok(quote!(
+        ::std::format!("{{ {} }}", [#(::std::format!("{} : {}", #names, #types)),*].join(", "))
+    ))
+}
+
+/// Tokens evaluating to the Dhall union type of `variants` as a `String`, named as serde serializes them with the `rename_all` rule of their container
+fn tokenize_union<'a>(variants: impl Iterator<Item=&'a syn::Variant>, rename_all: Option<&str>, type_parameters: &[Ident]) -> Result<TokenStream, Error> {
+    let mut alternatives = Vec::new();
+    for variant in variants.filter(|variant| !is_skipped(&variant.attrs, SerdeDirection::Serialize)) {
+        let name = serde_name(&variant.ident, &variant.attrs, rename_all, true, SerdeDirection::Serialize);
+        alternatives.push(match &variant.fields {
+            syn::Fields::Unit => quote!(::std::string::String::from(#name)),
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let (ty, _) = tokenize_dhall_type(&fields.unnamed[0].ty, type_parameters)?;
+                quote!(::std::format!("{} : {}", #name, #ty))
+            },
+            syn::Fields::Unnamed(fields) => return Err(Error::new_spanned("Variants with more than one field have no Dhall equivalent", fields.span())),
+            fields @ syn::Fields::Named(_) => {
+                // The fields of struct variants follow the variant's own `rename_all`
+                let record = tokenize_record(fields, rename_all_rule(&variant.attrs, SerdeDirection::Serialize).as_deref(), type_parameters)?;
+                quote!(::std::format!("{} : {}", #name, #record))
+            },
+        });
+    }
+
+    if alternatives.is_empty() {
+        return Ok(quote!(::std::string::String::from("<>")));
+    }
+    Ok(quote!(
+        ::std::format!("< {} >", [#(#alternatives),*].join(" | "))
+    ))
+}
+
+/// Implement `#[derive(DhallSchema)]`, generating functions which write the Dhall type (or schema) of a Rust type
+pub fn derive_dhall_schema(input: &syn::DeriveInput) -> Result<TokenStream, Error> {
+    let options = parse_options(&input.attrs)?;
+    let type_parameters: Vec<Ident> = input.generics.type_params().map(|param| param.ident.clone()).collect();
+    if type_parameters.len() > 1 {
+        return Err(Error::new_spanned("Only a single type parameter per type is supported", input.generics.span()));
+    }
+    if options.default && !type_parameters.is_empty() {
+        return Err(Error::new_spanned("Schemas with defaults are not supported for generic types", input.generics.span()));
+    }
+
+    let rename_all = rename_all_rule(&input.attrs, SerdeDirection::Serialize);
+    let dhall_type = match &input.data {
+        syn::Data::Struct(data) => tokenize_record(&data.fields, rename_all.as_deref(), &type_parameters)?,
+        syn::Data::Enum(data) => tokenize_union(data.variants.iter(), rename_all.as_deref(), &type_parameters)?,
+        syn::Data::Union(data) => return Err(Error::new_spanned("Unions have no Dhall equivalent", data.union_token.span())),
+    };
+    let type_lambdas = type_parameters.iter().map(|param| format!("\\({} : Type) -> ", param)).collect::<String>();
+
+    let ident = &input.ident;
+    let file_name = format!("{}.dhall", ident.unraw().to_string().to_case(Case::Snake));
+    let (type_import, schema) = if options.default {
+        (
+            format!("(./{}).Type", file_name),
+            quote!(
+                let default = ::serde_dhall::serialize(&<Self as ::core::default::Default>::default()).static_type_annotation().to_string()?;
+                ::core::result::Result::Ok(::std::format!("{{ Type = {}, default = {} }}", Self::dhall_type(), default))
+            ),
+        )
+    } else {
+        (format!("./{}", file_name), quote!(::core::result::Result::Ok(Self::dhall_type())))
+    };
+    let schema_bounds = if options.default {
+        quote!(where Self: ::core::default::Default + ::serde::Serialize + ::serde_dhall::StaticType)
+    } else {
+        TokenStream::new()
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The Dhall type of this type, importing other `DhallSchema` types from their .dhall files
+            pub fn dhall_type() -> ::std::string::String {
+                ::std::format!("{}{}", #type_lambdas, #dhall_type)
+            }
+
+            /// The name of the .dhall file this type is written to
+            pub fn dhall_schema_file_name() -> &'static str {
+                #file_name
+            }
+
+            /// The expression other .dhall files in the same directory use to refer to this type
+            pub fn dhall_type_import() -> ::std::string::String {
+                ::std::string::String::from(#type_import)
+            }
+
+            /// The contents of this type's .dhall file: its Dhall type, or a `{ Type, default }` schema with `#[dhall_schema(default)]`
+            pub fn dhall_schema() -> ::serde_dhall::Result<::std::string::String> #schema_bounds {
+                #schema
+            }
+
+            /// Write this type's .dhall file into `directory`
+            pub fn write_dhall_schema(directory: impl ::core::convert::AsRef<::std::path::Path>) -> ::std::io::Result<()> #schema_bounds {
+                let schema = Self::dhall_schema().map_err(|error| ::std::io::Error::new(::std::io::ErrorKind::InvalidData, error.to_string()))?;
+                ::std::fs::write(directory.as_ref().join(Self::dhall_schema_file_name()), schema + "\n")
+            }
+        }
+    ))
+}
@@ -0,0 +1,338 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::ToTokens;
+use serde_dhall::SimpleType;
+use syn::ext::IdentExt;
+
+use crate::aggregate_type::{metadata_content, AggregateKind, AggregateTypeInfo, DhallTypeDisplay};
+use crate::dhall_schema::{generic_arguments, primitive_dhall_type};
+use crate::type_origin::TypeOrigin;
+use crate::util::Error;
+
+/// A struct or enum written in the `dhall_types` module, which is used instead of generating the type of the same name
+#[derive(Debug)]
+pub(crate) struct HandWrittenType {
+    ident: Ident,
+    shape: Shape,
+}
+
+#[derive(Debug)]
+enum Shape {
+    Struct(Vec<Member>),
+    Enum(Vec<Member>),
+    // Tuple and unit structs, which aren't checked against the Dhall type
+    Other,
+}
+
+/// A field or variant, by the names serde deserializes it from
+#[derive(Debug)]
+struct Member {
+    name: String,
+    aliases: Vec<String>,
+    span: Span,
+    payload: Payload,
+}
+
+impl Member {
+    fn new(ident: &Ident, attrs: &[syn::Attribute], rename_all: Option<&str>, is_variant: bool, payload: Payload) -> Self {
+        Self {
+            name: serde_name(ident, attrs, rename_all, is_variant, SerdeDirection::Deserialize),
+            aliases: serde_aliases(attrs),
+            span: ident.span(),
+            payload,
+        }
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|alias| alias == name)
+    }
+}
+
+#[derive(Debug)]
+enum Payload {
+    None,
+    // The type of a field or newtype variant, as tokens since `syn::Type` isn't `Debug`
+    Type(TokenStream),
+    // Struct variants and tuple variants with several fields, whose fields aren't checked
+    Fields,
+}
+
+/// The Rust names of the named Dhall records and unions of a `dhall_types` module, to check fields and payloads referring to them
+pub(crate) struct NamedTypes<'n> {
+    /// The name of the Rust type used for a Dhall type, if it is a named record or union
+    pub rust_name: &'n dyn Fn(&SimpleType) -> Option<String>,
+    /// The names of the Rust types used for every named record and union
+    pub names: HashSet<String>,
+}
+
+impl HandWrittenType {
+    /// Collect the structs and enums written in the module
+    pub fn collect(items: &[syn::Item]) -> Vec<Self> {
+        items.iter().filter_map(Self::new).collect()
+    }
+
+    /// The hand-written type for `item`, if it is a struct or enum
+    pub fn new(item: &syn::Item) -> Option<Self> {
+        match item {
+            syn::Item::Struct(item) => {
+                let rename_all = rename_all_rule(&item.attrs, SerdeDirection::Deserialize);
+                Some(Self {
+                    ident: item.ident.clone(),
+                    shape: match &item.fields {
+                        syn::Fields::Named(fields) => Shape::Struct(fields.named.iter()
+                            .filter(|field| !is_skipped(&field.attrs, SerdeDirection::Deserialize))
+                            .map(|field| {
+                                let ident = field.ident.as_ref().expect("Named fields have idents");
+                                Member::new(ident, &field.attrs, rename_all.as_deref(), false, Payload::Type(field.ty.to_token_stream()))
+                            })
+                            .collect()),
+                        _ => Shape::Other,
+                    },
+                })
+            },
+            syn::Item::Enum(item) => {
+                let rename_all = rename_all_rule(&item.attrs, SerdeDirection::Deserialize);
+                Some(Self {
+                    ident: item.ident.clone(),
+                    shape: Shape::Enum(item.variants.iter()
+                        .filter(|variant| !is_skipped(&variant.attrs, SerdeDirection::Deserialize))
+                        .map(|variant| {
+                            let payload = match &variant.fields {
+                                syn::Fields::Unit => Payload::None,
+                                syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Payload::Type(fields.unnamed[0].ty.to_token_stream()),
+                                _ => Payload::Fields,
+                            };
+                            Member::new(&variant.ident, &variant.attrs, rename_all.as_deref(), true, payload)
+                        })
+                        .collect()),
+                })
+            },
+            _ => None,
+        }
+    }
+
+    pub fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    /// Whether the shape of the type can be checked, which tuple and unit structs can't
+    pub fn is_checkable(&self) -> bool {
+        !matches!(self.shape, Shape::Other)
+    }
+
+    /// Check the fields or variants of the type against the Dhall record or union `info` it is used for,
+    /// by name and, where the Rust type is a primitive, `Option`, `Vec`, a map or one of the `named_types`, by type
+    pub fn check(&self, info: AggregateTypeInfo, origin: &TypeOrigin, named_types: Option<&NamedTypes>) -> Vec<Error> {
+        let (members, kind) = match (&self.shape, info.kind()) {
+            (Shape::Struct(members), AggregateKind::Record) => (members, "field"),
+            (Shape::Enum(members), AggregateKind::Union) => (members, "variant"),
+            (Shape::Other, _) => return Vec::new(),
+            (Shape::Struct(_), AggregateKind::Union) => return vec![Error::new_spanned(format!("Hand-written struct '{}' is used for a Dhall union (from '{}'), it should be an enum", self.ident, origin), self.ident.span())],
+            (Shape::Enum(_), AggregateKind::Record) => return vec![Error::new_spanned(format!("Hand-written enum '{}' is used for a Dhall record (from '{}'), it should be a struct", self.ident, origin), self.ident.span())],
+        };
+        let mut dhall_members = match info.iter_members() {
+            Ok(dhall_members) => dhall_members.collect::<Vec<_>>(),
+            Err(e) => return vec![e],
+        };
+        // Report in a stable order
+        dhall_members.sort_by_key(|(member_name, _)| *member_name);
+
+        let mut errors = Vec::new();
+        for (member_name, member_type) in dhall_members.iter() {
+            let dhall_member = match member_type {
+                Some(member_type) => format!("`{} : {}`", member_name, DhallTypeDisplay(member_type)),
+                None => format!("`{}`", member_name),
+            };
+            let member = match members.iter().find(|member| member.has_name(member_name)) {
+                Some(member) => member,
+                None => {
+                    errors.push(Error::new_spanned(format!("Hand-written type '{}' has no {} for {} (from '{}')", self.ident, kind, dhall_member, origin), self.ident.span()));
+                    continue;
+                },
+            };
+            let matches = match (&member.payload, member_type) {
+                (Payload::None, None) | (Payload::Fields, Some(_)) => true,
+                (Payload::Type(ty), Some(member_type)) => match syn::parse2::<syn::Type>(ty.clone()) {
+                    Ok(ty) => type_matches(&ty, member_type, named_types),
+                    Err(e) => {
+                        errors.push(Error::new_spanned(format!("The type of the {} '{}' of hand-written type '{}' can't be checked: {}", kind, member.name, self.ident, e), member.span));
+                        continue;
+                    },
+                },
+                _ => false,
+            };
+            if !matches {
+                errors.push(Error::new_spanned(format!("The {} '{}' of hand-written type '{}' doesn't match the Dhall {} (from '{}')", kind, member.name, self.ident, dhall_member, origin), member.span));
+            }
+        }
+        for member in members.iter().filter(|member| dhall_members.iter().all(|(member_name, _)| !member.has_name(member_name))) {
+            errors.push(Error::new_spanned(format!("The {} '{}' of hand-written type '{}' isn't in the Dhall type (from '{}')", kind, member.name, self.ident, origin), member.span));
+        }
+        errors
+    }
+}
+
+/// Which way serde converts a value, as fields and variants can be renamed or skipped in one direction only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SerdeDirection {
+    Serialize,
+    Deserialize,
+}
+
+/// Whether serde skips a field or variant in `direction`
+pub(crate) fn is_skipped(attrs: &[syn::Attribute], direction: SerdeDirection) -> bool {
+    let directed_skip = match direction {
+        SerdeDirection::Serialize => "skip_serializing",
+        SerdeDirection::Deserialize => "skip_deserializing",
+    };
+    serde_attributes(attrs).any(|meta| matches!(meta, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("skip") || path.is_ident(directed_skip)))
+}
+
+/// The rule of a container's `#[serde(rename_all = "...")]` in `direction`, which renames its fields or variants
+pub(crate) fn rename_all_rule(attrs: &[syn::Attribute], direction: SerdeDirection) -> Option<String> {
+    serde_string(attrs, "rename_all", direction)
+}
+
+/// The name serde gives a field or variant in `direction`, from `#[serde(rename = "...")]`, the container's `rename_all` rule, or its ident
+pub(crate) fn serde_name(ident: &Ident, attrs: &[syn::Attribute], rename_all: Option<&str>, is_variant: bool, direction: SerdeDirection) -> String {
+    if let Some(name) = serde_string(attrs, "rename", direction) {
+        return name;
+    }
+    let name = ident.unraw().to_string();
+    match rename_all {
+        Some(rule) if is_variant => rename_variant(&name, rule),
+        Some(rule) => rename_field(&name, rule),
+        None => name,
+    }
+}
+
+/// Applies a `rename_all` rule to a variant the way serde does, splitting the PascalCase name before each uppercase character only
+fn rename_variant(variant: &str, rule: &str) -> String {
+    match rule {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "camelCase" => lowercase_first(variant),
+        "snake_case" => {
+            let mut snake = String::new();
+            for (i, ch) in variant.char_indices() {
+                if i > 0 && ch.is_uppercase() {
+                    snake.push('_');
+                }
+                snake.push(ch.to_ascii_lowercase());
+            }
+            snake
+        },
+        "SCREAMING_SNAKE_CASE" => rename_variant(variant, "snake_case").to_ascii_uppercase(),
+        "kebab-case" => rename_variant(variant, "snake_case").replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => rename_variant(variant, "SCREAMING_SNAKE_CASE").replace('_', "-"),
+        _ => variant.to_string(),
+    }
+}
+
+/// Applies a `rename_all` rule to a field the way serde does, splitting the snake_case name at underscores only
+fn rename_field(field: &str, rule: &str) -> String {
+    match rule {
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => {
+            let mut pascal = String::new();
+            let mut capitalize = true;
+            for ch in field.chars() {
+                if ch == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    pascal.push(ch.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    pascal.push(ch);
+                }
+            }
+            pascal
+        },
+        "camelCase" => lowercase_first(&rename_field(field, "PascalCase")),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => field.to_string(),
+    }
+}
+
+fn lowercase_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// The other names serde accepts for a field or variant when deserializing, from `#[serde(alias = "...")]`
+fn serde_aliases(attrs: &[syn::Attribute]) -> Vec<String> {
+    serde_attributes(attrs).filter_map(|meta| match meta {
+        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, lit: syn::Lit::Str(alias), .. })) if path.is_ident("alias") => Some(alias.value()),
+        _ => None,
+    }).collect()
+}
+
+/// The value of a serde attribute given as `key = "..."`, or as `key(serialize = "...", deserialize = "...")` for the two directions
+fn serde_string(attrs: &[syn::Attribute], key: &str, direction: SerdeDirection) -> Option<String> {
+    let direction_key = match direction {
+        SerdeDirection::Serialize => "serialize",
+        SerdeDirection::Deserialize => "deserialize",
+    };
+    serde_attributes(attrs).find_map(|meta| match meta {
+        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, lit: syn::Lit::Str(value), .. })) if path.is_ident(key) => Some(value.value()),
+        syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident(key) => list.nested.iter().find_map(|nested| match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, lit: syn::Lit::Str(value), .. })) if path.is_ident(direction_key) => Some(value.value()),
+            _ => None,
+        }),
+        _ => None,
+    })
+}
+
+fn serde_attributes(attrs: &[syn::Attribute]) -> impl Iterator<Item=syn::NestedMeta> + '_ {
+    attrs.iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+}
+
+/// Whether the Rust type `ty` can hold the Dhall type `st`. Primitives, `Option`, `Vec`, `HashMap` and `BTreeMap` are compared,
+/// as are the Rust types of the `named_types` records and unions. Any other type is assumed to match.
+fn type_matches(ty: &syn::Type, st: &SimpleType, named_types: Option<&NamedTypes>) -> bool {
+    let st = metadata_content(st).unwrap_or(st);
+    let path = match ty {
+        syn::Type::Reference(reference) => return type_matches(&reference.elem, st, named_types),
+        syn::Type::Paren(paren) => return type_matches(&paren.elem, st, named_types),
+        syn::Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return true,
+    };
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return true,
+    };
+    match (segment.ident.to_string().as_str(), generic_arguments(segment).as_slice()) {
+        ("Box" | "Rc" | "Arc", [ty]) => type_matches(ty, st, named_types),
+        ("Option", [ty]) => matches!(st, SimpleType::Optional(st) if type_matches(ty, st, named_types)),
+        ("Vec", [ty]) => matches!(st, SimpleType::List(st) if type_matches(ty, st, named_types)),
+        // serde_dhall reads maps from records whose fields all have the type of the values, and from `toMap` style lists of `{ mapKey : Text, mapValue : T }`
+        ("HashMap" | "BTreeMap", [key, value]) => type_matches(key, &SimpleType::Text, named_types) && match st {
+            SimpleType::List(entry) => match &**entry {
+                SimpleType::Record(fields) if fields.len() == 2 && fields.get("mapKey") == Some(&SimpleType::Text) => fields.get("mapValue").is_some_and(|st| type_matches(value, st, named_types)),
+                _ => false,
+            },
+            SimpleType::Record(fields) => fields.values().all(|st| type_matches(value, st, named_types)),
+            _ => false,
+        },
+        (name, []) if primitive_dhall_type(name).is_some() => primitive_dhall_type(name) == Some(DhallTypeDisplay(st).to_string().as_str()),
+        (name, _) => match named_types {
+            Some(named_types) => match (named_types.rust_name)(st) {
+                Some(rust_name) => rust_name == name,
+                // A named record or union can't hold any other Dhall type. Anonymous records and unions may have the same structure
+                None => matches!(st, SimpleType::Record(_) | SimpleType::Union(_)) || !named_types.names.contains(name),
+            },
+            None => true,
+        },
+    }
+}
@@ -1,10 +1,14 @@
 mod aggregate_type;
 mod appendlist;
+mod dhall_schema;
+mod hand_written;
 mod named_type;
 mod options;
 mod schema;
+mod source_docs;
 mod type_collection;
 mod type_gen;
+mod type_origin;
 mod util;
 
 use std::collections::HashMap;
@@ -12,9 +16,14 @@ use std::ffi;
 use std::path;
 use std::path::Path;
 use appendlist::AppendList;
+use hand_written::HandWrittenType;
+use options::MappedType;
 use options::Options;
+use options::Pointer;
+use options::Visibility;
 use proc_macro2::Ident;
 use proc_macro2::Span;
+use quote::ToTokens;
 use quote::quote_spanned;
 use serde_dhall::SimpleType;
 use syn::Token;
@@ -24,33 +33,42 @@ use quote::quote;
 use convert_case::{Case, Casing};
 
 use aggregate_type::AggregateType;
+use aggregate_type::alias_metadata;
+use aggregate_type::pointer_metadata;
+use aggregate_type::simple_type_is_open_generic;
 use named_type::NamedType;
 use schema::Schema;
 use type_collection::PrimitiveIdents;
 use type_collection::TypeCollection;
 use type_gen::TypeGen;
-use util::create_error;
+use type_origin::TypeOrigin;
+use util::Error;
 
 #[derive(Debug, Default)]
 struct Spanned<T> {
     pub value: T,
-    pub _span: Option<Span>,
+    pub span: Option<Span>,
 }
 
 impl<T> Spanned<T> {
     pub fn new(value: T) -> Self {
         Self {
             value,
-            _span: None
+            span: None
         }
     }
 
     pub fn new_spanned(value: T, span: Span) -> Self {
         Self {
             value, 
-            _span: Some(span),
+            span: Some(span),
         }
     }
+
+    /// Was the value given explicitly, rather than being a default?
+    pub fn is_explicit(&self) -> bool {
+        self.span.is_some()
+    }
 }
 
 #[allow(dead_code)]
@@ -91,7 +109,7 @@ struct DhallTypesAttribute {
 }
 
 impl DhallTypesAttribute {
-    pub fn into_options(self) -> Result<Options, TokenStream> {
+    pub fn into_options(self) -> Result<Options, Error> {
         let mut options = Options::new(self.path.value().into(), self.path.span());
         if let Some(DhallTypesAttributeOptions { options: Some(attribute_options), .. }) = self.options {
             for option in attribute_options.into_iter() {
@@ -100,28 +118,28 @@ impl DhallTypesAttribute {
                         if let syn::Lit::Bool(value) = option.value {
                             options.anonymous_enum_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "named_enum_impl" => {
                         if let syn::Lit::Bool(value) = option.value {
                             options.named_enum_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "anonymous_struct_impl" => {
                         if let syn::Lit::Bool(value) = option.value {
                             options.anonymous_struct_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "named_struct_impl" => {
                         if let syn::Lit::Bool(value) = option.value {
                             options.named_struct_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "anonymous_impl" => {
@@ -129,7 +147,7 @@ impl DhallTypesAttribute {
                             options.anonymous_enum_impl = Spanned::new_spanned(value.value(), value.span());
                             options.anonymous_struct_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "named_impl" => {
@@ -137,7 +155,7 @@ impl DhallTypesAttribute {
                             options.named_enum_impl = Spanned::new_spanned(value.value(), value.span());
                             options.named_struct_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "enum_impl" => {
@@ -145,7 +163,7 @@ impl DhallTypesAttribute {
                             options.anonymous_enum_impl = Spanned::new_spanned(value.value(), value.span());
                             options.named_enum_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "struct_impl" => {
@@ -153,7 +171,7 @@ impl DhallTypesAttribute {
                             options.anonymous_struct_impl = Spanned::new_spanned(value.value(), value.span());
                             options.named_struct_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
                     "impl" => {
@@ -163,15 +181,147 @@ impl DhallTypesAttribute {
                             options.anonymous_struct_impl = Spanned::new_spanned(value.value(), value.span());
                             options.named_struct_impl = Spanned::new_spanned(value.value(), value.span());
                         } else {
-                            return Err(util::create_spanned_error("Expected boolean literal", option.option.span()));
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
+                        }
+                    }
+                    "inline_variant_records" => {
+                        if let syn::Lit::Bool(value) = option.value {
+                            options.inline_variant_records = Spanned::new_spanned(value.value(), value.span());
+                        } else {
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
+                        }
+                    }
+                    "alias_newtypes" => {
+                        if let syn::Lit::Bool(value) = option.value {
+                            options.alias_newtypes = Spanned::new_spanned(value.value(), value.span());
+                        } else {
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
                         }
                     }
-                    _ => return Err(util::create_spanned_error(&format!("Unknown option '{}'", option.option), option.option.span())),
+                    "boxed" => {
+                        options.pointers.extend(Self::parse_pointer_option(&option.value, Pointer::Box)?);
+                    }
+                    "rc" => {
+                        options.pointers.extend(Self::parse_pointer_option(&option.value, Pointer::Rc)?);
+                    }
+                    "arc" => {
+                        options.pointers.extend(Self::parse_pointer_option(&option.value, Pointer::Arc)?);
+                    }
+                    "box_variants_over" => {
+                        if let syn::Lit::Int(value) = option.value {
+                            let size = value.base10_parse().map_err(|e| Error::new_spanned(e.to_string(), value.span()))?;
+                            options.box_variants_over = Spanned::new_spanned(Some(size), value.span());
+                        } else {
+                            return Err(Error::new_spanned("Expected integer literal", option.value.span()));
+                        }
+                    }
+                    "check_hand_written_types" => {
+                        if let syn::Lit::Bool(value) = option.value {
+                            options.check_hand_written_types = Spanned::new_spanned(value.value(), value.span());
+                        } else {
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
+                        }
+                    }
+                    "catch_all_variants" => {
+                        match option.value {
+                            syn::Lit::Bool(value) => options.catch_all_variants = Spanned::new_spanned(value.value(), value.span()),
+                            syn::Lit::Str(value) => {
+                                for name in value.value().split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                                    if syn::parse_str::<Ident>(name).is_err() {
+                                        return Err(Error::new_spanned(format!("'{}' is not a type name", name), value.span()));
+                                    }
+                                    options.catch_all_types.push(Spanned::new_spanned(name.to_owned(), value.span()));
+                                }
+                            },
+                            value => return Err(Error::new_spanned("Expected boolean or string literal", value.span())),
+                        }
+                    }
+                    "kind_enums" => {
+                        if let syn::Lit::Bool(value) = option.value {
+                            options.kind_enums = Spanned::new_spanned(value.value(), value.span());
+                        } else {
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
+                        }
+                    }
+                    "struct_constructors" => {
+                        if let syn::Lit::Bool(value) = option.value {
+                            options.struct_constructors = Spanned::new_spanned(value.value(), value.span());
+                        } else {
+                            return Err(Error::new_spanned("Expected boolean literal", option.value.span()));
+                        }
+                    }
+                    "type_map" => {
+                        options.type_map.push(Self::parse_type_map_option(&option.value)?);
+                    }
+                    "type_visibility" => {
+                        options.type_visibility = Self::parse_visibility_option(&option.value)?;
+                    }
+                    "field_visibility" => {
+                        options.field_visibility = Self::parse_visibility_option(&option.value)?;
+                    }
+                    _ => return Err(Error::new_spanned(format!("Unknown option '{}'", option.option), option.option.span())),
                 }
             }
         }
         Ok(options)
     }
+
+    /// A comma-separated list of type names and `Type.member` paths to wrap in `pointer`
+    fn parse_pointer_option(value: &syn::Lit, pointer: Pointer) -> Result<Vec<(String, Spanned<Pointer>)>, Error> {
+        if let syn::Lit::Str(value) = value {
+            value.value().split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(|path| if path.split('.').all(|segment| syn::parse_str::<Ident>(segment).is_ok()) {
+                    Ok((path.to_owned(), Spanned::new_spanned(pointer, value.span())))
+                } else {
+                    Err(Error::new_spanned(format!("'{}' is not a type name or a `Type.member` path", path), value.span()))
+                })
+                .collect()
+        } else {
+            Err(Error::new_spanned("Expected string literal", value.span()))
+        }
+    }
+
+    /// Parse `"Key = path::to::Type"`, or `"Key = path::to::Type with path::to::adapter"` for a type with a `#[serde(with = "...")]` adapter
+    fn parse_type_map_option(value: &syn::Lit) -> Result<(String, Spanned<MappedType>), Error> {
+        let value = match value {
+            syn::Lit::Str(value) => value,
+            _ => return Err(Error::new_spanned("Expected string literal", value.span())),
+        };
+        let text = value.value();
+        let (path, ty) = text.split_once('=')
+            .ok_or_else(|| Error::new_spanned("Expected \"Type = path::to::RustType\" or \"Type.member = path::to::RustType\"", value.span()))?;
+        let path = path.trim();
+        if !path.split('.').all(|segment| syn::parse_str::<Ident>(segment).is_ok()) {
+            return Err(Error::new_spanned(format!("'{}' is not a type name or a `Type.member` path", path), value.span()));
+        }
+        let (ty, with) = match ty.rsplit_once(" with ") {
+            Some((ty, with)) => (ty, Some(with.trim())),
+            None => (ty, None),
+        };
+        let ty = syn::parse_str::<syn::Type>(ty.trim())
+            .map_err(|e| Error::new_spanned(format!("Invalid Rust type for '{}': {}", path, e), value.span()))?;
+        if let Some(with) = with {
+            syn::parse_str::<syn::Path>(with)
+                .map_err(|e| Error::new_spanned(format!("Invalid serde adapter module for '{}': {}", path, e), value.span()))?;
+        }
+        let mapped = MappedType {
+            ty: ty.into_token_stream(),
+            with: with.map(str::to_owned),
+        };
+        Ok((path.to_owned(), Spanned::new_spanned(mapped, value.span())))
+    }
+
+    fn parse_visibility_option(value: &syn::Lit) -> Result<Spanned<Visibility>, Error> {
+        if let syn::Lit::Str(value) = value {
+            value.value().parse()
+                .map(|visibility| Spanned::new_spanned(visibility, value.span()))
+                .map_err(|_| Error::new_spanned("Expected one of \"pub\", \"pub(crate)\", \"pub(super)\", or \"private\"", value.span()))
+        } else {
+            Err(Error::new_spanned("Expected string literal", value.span()))
+        }
+    }
 }
 
 impl syn::parse::Parse for DhallTypesAttribute {
@@ -188,10 +338,23 @@ pub(crate) const META_FIELD_PREFIX_NAME: &str = "__rust_type_name";
 pub(crate) const META_FIELD_PREFIX_CONTENT: &str = "__rust_type_content";
 pub(crate) const META_ENV_POSTFIX: &str = "rust_struct";
 pub(crate) const META_FIELD_POSTFIX: &str = "__rust_struct";
+pub(crate) const META_ENV_PHANTOM: &str = "rust_phantom";
+pub(crate) const META_FIELD_PHANTOM: &str = "__rust_phantom";
+pub(crate) const META_ENV_RECURSIVE: &str = "rust_recursive";
+pub(crate) const META_FIELD_RECURSIVE: &str = "__rust_recursive";
+pub(crate) const META_ENV_POINTER: &str = "rust_pointer";
+pub(crate) const META_FIELD_POINTER_KIND: &str = "__rust_pointer_kind";
+pub(crate) const META_FIELD_POINTER_CONTENT: &str = "__rust_pointer_content";
+pub(crate) const META_ENV_CATCH_ALL: &str = "rust_catch_all";
+pub(crate) const META_FIELD_CATCH_ALL: &str = "__rust_catch_all";
 
 fn set_environment_variable() {
     std::env::set_var(META_ENV_PREFIX, format!("\\(Name: Type) -> \\(Content: Type) -> {{ {}: Name, {}: Content }}", META_FIELD_PREFIX_NAME, META_FIELD_PREFIX_CONTENT));
     std::env::set_var(META_ENV_POSTFIX, format!("\\(Name: Type) -> {{ {}: Name }}", META_FIELD_POSTFIX));
+    std::env::set_var(META_ENV_PHANTOM, format!("\\(T: Type) -> {{ {}: T }}", META_FIELD_PHANTOM));
+    std::env::set_var(META_ENV_RECURSIVE, format!("\\(Name: Type) -> {{ {}: Name }}", META_FIELD_RECURSIVE));
+    std::env::set_var(META_ENV_POINTER, format!("\\(Pointer: Type) -> \\(Content: Type) -> {{ {}: Pointer, {}: Content }}", META_FIELD_POINTER_KIND, META_FIELD_POINTER_CONTENT));
+    std::env::set_var(META_ENV_CATCH_ALL, format!("\\(Union: Type) -> {{ {}: Union }}", META_FIELD_CATCH_ALL));
 }
 
 /// Create serialization-compatible Rust types in the following `mod` for the Dhall types contained in a given folder.
@@ -200,8 +363,8 @@ fn set_environment_variable() {
 /// Any sub-unions or records contained within these files will be assigned an arbitrary name by default (which may change between compiles).
 ///  
 /// ### Example `my_dhall_stuff.rs`
-/// ``` rust
-///     #[serde_dhall_typegen::dhall_types("./dhall/schema/")]
+/// ```rust
+///     #[serde_dhall_typegen::dhall_types("./tests/dhall/schema/")]
 ///     mod dhall { }
 /// ```
 /// 
@@ -216,11 +379,69 @@ fn set_environment_variable() {
 ///     * `named_struct_impl = bool` - Should member access functions be generated for anonymous structs? Defaults to `false`
 ///     * `anonymous_enum_impl = bool` - Should member access functions be generated for anonymous enums? Defaults to `true`
 ///     * `anonymous_struct_impl = bool` - Should member access functions be generated for anonymous structs? Defaults to `false`
+///     * `struct_constructors = bool` - Should a `new` function and consuming `with_*` setters be generated for structs? Defaults to `false`
+///     * `type_visibility = "..."` - The visibility of generated types: `"pub"`, `"pub(crate)"`, `"pub(super)"`, or `"private"`. Defaults to `"pub"`
+///     * `field_visibility = "..."` - The visibility of generated struct fields, with the same values as `type_visibility`. Defaults to `"pub"`. 
+///       If fields are not `pub`, struct member access functions are generated unless `named_struct_impl`/`anonymous_struct_impl` (or an option implying them) is explicitly set
+///     * `inline_variant_records = bool` - Should anonymous record payloads of union alternatives be generated as struct variants (`Rect { height: u64, width: u64 }`) instead of separate anonymous structs? Defaults to `false`
+///     * `alias_newtypes = bool` - Should files evaluating to types other than records and unions generate `#[serde(transparent)]` newtypes instead of type aliases? Defaults to `false`
+///     * `boxed = "..."`, `rc = "..."`, `arc = "..."` - Comma-separated type names and `Type.member` paths to wrap in `Box`, `Rc` or `Arc` (see Pointers)
+///     * `box_variants_over = integer` - Box the payloads of union alternatives estimated to be larger than this many bytes, e.g. `200` to avoid clippy's `large_enum_variant` lint
+///     * `type_map = "Name = path::to::Type"` - Use an existing Rust type for a named type, type alias or `Type.member` path instead of generating one, optionally with a serde adapter (see Type Map). May be given more than once
+///     * `check_hand_written_types = bool` - Should structs and enums written in the module be checked against the Dhall types they replace (see Hand-Written Types for which Rust types are compared)? Defaults to `true`
+///     * `catch_all_variants = bool` or `catch_all_variants = "..."` - Add a catch-all `Unknown` variant to every generated enum, or to the comma-separated named enums, for alternatives added to the Dhall type later (see Catch-All Variants). Unions marked with `env:rust_catch_all` get one either way. Defaults to `false`
+///     * `kind_enums = bool` - Generate a nullary `<Enum>Kind` enum for every enum whose variants have values, naming its alternatives, and `kind()` and `name()` functions on every enum (see Kind Enums). Defaults to `false`
 /// 
 /// # Dhall Input
 /// 
 /// Each .dhall file in the specified directory will be evaluated and must return a record, a union, a [schema](https://docs.dhall-lang.org/tutorials/Language-Tour.html#record-completion),
 /// or a function taking a single `Type` parameter which returns one of the previous types.
+///
+/// Files evaluating to any other type, like `Natural` or `List Text`, generate type aliases.
+///
+/// # Type Aliases
+///
+/// A file `user_id.dhall` containing `Natural` generates `pub type UserId = u64;`, and a generic file containing `\(T: Type) -> List T` generates `pub type MyList<T> = Vec<T>;`.  
+/// With `alias_newtypes = true`, a `#[serde(transparent)]` newtype is generated instead, implementing `From` in both directions, `Deref`, `StaticType`, and the loader and writer functions:
+///
+/// ```rust
+/// pub struct UserId(pub u64);
+/// ```
+///
+/// Since Dhall types are structural, a field of type `Natural` could mean any alias of `Natural`, so fields only use an alias when the type is named with metadata. 
+/// The name given with metadata takes precedence over the file name:
+///
+/// ### email.dhall
+/// ``` dhall
+///     let rust_type = env:rust_type ? (\(T: Type)->\(T: Type)->T)
+///     in
+///     rust_type <Email> Text
+/// ```
+///
+/// ### account.dhall
+/// ``` dhall
+///     {
+///       id: Natural,
+///       email: ./email.dhall,
+///       backup_emails: List ./email.dhall,
+///     }
+/// ```
+///
+/// ... generates...
+///
+/// ```rust
+///     pub type Email = String;
+///     pub struct Account {
+///       pub backup_emails: Vec<Email>,
+///       pub email: Email,
+///       pub id: u64,
+///     }
+/// ```
+///
+/// Records and unions within an alias (e.g. `List { name : Text }`) must be named with metadata.
+///
+/// A file applying a generic type, like `text_quantity.dhall` containing `let Quantity = ./quantity.dhall in Quantity Text`, generates `pub type TextQuantity = Quantity<String>;`
+/// (or a newtype with `alias_newtypes = true`), which is used wherever `Quantity Text` appears.
 /// 
 /// # Generic Types
 /// 
@@ -245,7 +466,7 @@ fn set_environment_variable() {
 /// 
 /// ... becomes...
 /// 
-/// ``` rust
+/// ```rust
 ///     pub struct MyGeneric<T> {
 ///       pub field: T,
 ///     }
@@ -254,27 +475,278 @@ fn set_environment_variable() {
 ///       b: MyGeneric<String>,
 ///     }
 /// ```
+///
+/// Records and unions nested in a generic type (including within `Optional` and `List`) which use the type parameter become generic anonymous types, e.g. `MyGenericAnon0<T>`.
+///
+/// If no field uses the type parameter, the struct gets a `#[serde(skip)] _marker: PhantomData<T>` field. Dhall erases unused type parameters, so `Id User` and `Id Order` have the same type,
+/// and references to such a type can only be resolved if the parameter is marked in the definition:
+///
+/// ### id.dhall
+/// ``` dhall
+///     \(T: Type) -> { id: Natural } //\\ (env:rust_phantom T ? {})
+/// ```
+///
+/// ... becomes...
+///
+/// ```rust
+/// #   use std::marker::PhantomData;
+/// #   #[derive(serde::Deserialize)]
+///     pub struct Id<T> {
+///       pub id: u64,
+///       #[serde(skip)]
+///       pub _marker: PhantomData<T>,
+///     }
+/// ```
+///
+/// ... and a field of type `Id User` becomes `Id<User>`.
 /// 
+/// # Recursive Types
+///
+/// Dhall types can't refer to themselves, but a type can mark where it would with `env:rust_recursive`, naming the type it refers to.
+/// Outside of `dhall_types` the placeholder is `<>`, so the Dhall type describes values up to a fixed depth, and applying the type to itself more times allows deeper values:
+///
+/// ## tree.dhall
+/// ``` dhall
+///     let rust_recursive = env:rust_recursive ? (\(Name: Type) -> <>)
+///     let Node = \(Child: Type) -> { value: Natural, children: List Child }
+///     in Node (Node (rust_recursive <Tree>))
+/// ```
+///
+/// ... becomes...
+///
+/// ```rust
+///     pub struct Tree {
+///       pub children: Vec<Tree>,
+///       pub value: u64,
+///     }
+/// ```
+///
+/// Any record or union matching the type, up to a placeholder, is the recursive type itself. References outside of a `List` are boxed, e.g. `Optional (rust_recursive <Chain>)` becomes `Option<Box<Chain>>`.
+///
+/// Values of recursive types have no single Dhall type, so types referring to them don't implement `StaticType`, and their loaders don't check the value against a type annotation.
+/// Recursive types referring to no other recursive types can still be written: `to_dhall_string` annotates the value with the Dhall type unrolled exactly as deep as the value, which is also available as:
+/// ```text
+/// fn dhall_type_with_depth(depth: usize) -> serde_dhall::SimpleType
+/// ```
+///
+/// # Pointers
+///
+/// Fields and alternative payloads can be wrapped in `Box`, `Rc` or `Arc`, to keep large enums small or to share parts of a configuration between threads:
+/// * Naming a type (e.g. `arc = "Settings"`) wraps every reference to it, including within `Option` and `Vec`
+/// * A `Type.member` path (e.g. `boxed = "Service.fallback"`) wraps a single field or alternative payload of a named type. Members of anonymous types are reached through the path of fields and alternatives leading to them, like `Config.database.pool`
+/// * `box_variants_over` boxes alternative payloads whose size, estimated from their Dhall type, is over the given number of bytes
+/// * `env:rust_pointer` wraps a type where it appears in the Dhall source:
+///
+/// ``` dhall
+///     let rust_pointer = env:rust_pointer ? (\(Pointer: Type) -> \(T: Type) -> T)
+///     in { name: Text, cache: rust_pointer <Rc> { size: Natural } }
+/// ```
+///
+/// Pointers don't change the Dhall type. Struct variants generated with `inline_variant_records` aren't wrapped, but their fields can be.
+/// Serde only implements `Serialize` and `Deserialize` for `Rc` and `Arc` with its `rc` feature, so crates using them need `serde = { version = "1", features = ["rc"] }`.
+///
+/// # Type Map
+///
+/// `type_map` uses an existing Rust type where the generated type would have been, for Dhall types with a better Rust equivalent:
+/// * Naming a type or type alias (e.g. `type_map = "Duration = std::time::Duration"`) replaces every reference to it, and the type isn't generated
+/// * A `Type.member` path (e.g. `type_map = "Server.address = std::net::IpAddr"`) replaces the type of a single field or alternative payload
+///
+/// The Rust type must deserialize from the Dhall value as it is. If its serde format is different, name a module with `serialize` and `deserialize` functions after `with`,
+/// and the field or alternative gets `#[serde(with = "...")]`:
+///
+/// ```rust
+/// #[serde_dhall_typegen::dhall_types("./tests/dhall/type_map/", type_map = "Server.timeout_ms = std::time::Duration with crate::millis")]
+/// mod dhall { }
+/// # mod millis {
+/// #     use serde::{Deserialize, Deserializer, Serialize, Serializer};
+/// #     use std::time::Duration;
+/// #     pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+/// #         (duration.as_millis() as u64).serialize(serializer)
+/// #     }
+/// #     pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+/// #         u64::deserialize(deserializer).map(Duration::from_millis)
+/// #     }
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// Adapters only apply to fields and alternatives of the mapped type itself, not within `Optional` or `List`.
+/// Types given with `type_map` needn't implement `StaticType`, the generated types build their Dhall type from the Dhall source instead.
+///
+/// # Hand-Written Types
+///
+/// Structs and enums written in the module are used instead of generating the types of the same name, and generated types refer to them.
+/// Like types given with `type_map`, they needn't implement `StaticType`:
+///
+/// ```rust
+/// #[serde_dhall_typegen::dhall_types("./tests/dhall/hand_written/")]
+/// mod dhall {
+///     // Used for color.dhall, `{ r: Natural, g: Natural, b: Natural }`
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+///     pub struct Color {
+///         pub r: u8,
+///         pub g: u8,
+///         pub b: u8,
+///     }
+/// }
+/// ```
+///
+/// Their fields and variants are checked against the Dhall type by the names serde deserializes them from, following `rename`, `rename_all` and `alias`,
+/// and by type where the Rust type is a primitive, `Option`, `Vec`, `HashMap` or `BTreeMap`. A field or payload whose Dhall type is a named record or union
+/// must use the Rust type generated, hand-written or mapped for it, and the Rust types of named records and unions can't be used for other Dhall types.
+/// Any other Rust type, such as a type alias or a newtype, is assumed to match.
+/// Fields and variants with `#[serde(skip)]` or `#[serde(skip_deserializing)]` are ignored. Set `check_hand_written_types = false` to skip the check.
+///
+/// # Catch-All Variants
+///
+/// A union gaining an alternative would otherwise make values using it fail to load in programs generated from the old type.
+/// With `catch_all_variants`, enums get a `#[non_exhaustive]` attribute and an `Unknown` variant, which any alternative the Dhall type doesn't have is loaded as,
+/// keeping its name and value:
+///
+/// ```rust
+/// #[serde_dhall_typegen::dhall_types("./tests/dhall/catch_all/", catch_all_variants = "Shape")]
+/// mod dhall { }
+/// ```
+///
+/// ```text
+/// // Generated for shape.dhall, `< Point | Circle: { radius: Natural } >`
+/// #[non_exhaustive]
+/// pub enum Shape {
+///     Point,
+///     Circle(ShapeAnon0),
+///     #[serde(skip_serializing)]
+///     Unknown(UnknownAlternative),
+/// }
+///
+/// // Generated once for the module
+/// pub struct UnknownAlternative {
+///     pub name: String,
+///     pub value: Option<serde_dhall::SimpleValue>,
+/// }
+/// ```
+///
+/// A union can also be given a catch-all variant where it is defined, with `env:rust_catch_all`:
+///
+/// ### status.dhall
+/// ``` dhall
+///     let rust_catch_all = env:rust_catch_all ? (\(Union: Type) -> Union)
+///     in rust_catch_all < Draft | Published: Natural >
+/// ```
+///
+/// A named union is marked inside its name, as `rust_type <Status> (rust_catch_all < ... >)`, or outside it.
+/// Enums with a catch-all variant implement `Deserialize` by hand, reading the alternative's name first, and their known alternatives with serde's derive.
+/// The generated loaders of types containing these enums don't check values against their Dhall type, as the newer alternatives wouldn't match it.
+/// `Unknown` isn't part of the Dhall type, so serializing it is an error, and it has no variant access functions.
+/// `UnknownAlternative` only hashes the alternative's name, as `SimpleValue` isn't `Hash`.
+///
+/// # Kind Enums
+///
+/// With `kind_enums = true`, every enum with values in its variants gets a companion enum naming its alternatives, for when the alternative matters but its value doesn't:
+///
+/// ```text
+/// // Generated for shape.dhall, `< Point | Circle: { radius: Natural } >`
+/// #[derive(Debug, Clone, Copy, PartialOrd, Ord, Eq, PartialEq, Hash)]
+/// pub enum ShapeKind {
+///     Circle,
+///     Point,
+/// }
+///
+/// impl Shape {
+///     pub fn kind(&self) -> ShapeKind
+///     pub fn name(&self) -> &'static str
+/// }
+/// ```
+///
+/// Kind enums have the same functions as other enums without values, like `ShapeKind::ALL` (see Generated Functions).
+/// An enum with a catch-all variant has a kind enum with an `Unknown` variant too.
+/// An enum whose variants have no values is its own kind, so it gets no kind enum, and its `kind()` returns the value itself.
+///
 /// # Generated Functions
 /// 
 /// Anonymous enums have functions generated for each variant by default. If a variant `MyVariant` has an associated type `T`, the enum will have the following functions:
-/// ``` rust
+/// ```text
 /// fn my_variant(&self) -> Option<&T>
 /// fn my_variant_mut(&mut self) -> Option<&mut T>
 /// fn into_my_variant(self) -> Result<T, Self>
 /// ```
 ///
 /// If the variant does not have an associated type, it will have the following function:
-/// ``` rust
+/// ```text
 /// fn is_my_variant(&self) -> bool
 /// ```
 ///
-/// If struct functions are enabled, a field `my_field` of type `T` will have the following functions:
-/// ``` rust
+/// A struct variant generated with `inline_variant_records` has all four functions, with its fields as a tuple in alphabetical order:
+/// ```text
+/// fn is_rect(&self) -> bool
+/// fn rect(&self) -> Option<(&u64, &u64)>
+/// fn rect_mut(&mut self) -> Option<(&mut u64, &mut u64)>
+/// fn into_rect(self) -> Result<(u64, u64), Self>
+/// ```
+///
+/// Enums whose variants all have no associated types, like C-like enums, are also `Copy` and ordered by variant name (unless they have a catch-all variant, whose `UnknownAlternative` is neither), and have the Dhall alternative names:
+/// ```text
+/// const ALL: [Self; N]
+/// const COUNT: usize
+/// fn as_str(&self) -> &'static str
+/// impl Display
+/// impl FromStr<Err = String>
+/// ```
+/// Dhall unions are unordered, so whatever the order of the Dhall source, the variants are declared by name: `ALL` lists them in that order, which `Ord` follows too.
+/// `Display` and `FromStr` use `as_str`. A catch-all variant isn't in `ALL` or `COUNT`, and can't be parsed. Its `as_str` is `"Unknown"`, the alternative's own name is in its `UnknownAlternative`.
+///
+/// An enum converts from the type of a variant with `From`, and back with `TryFrom`, which gives back the enum for other variants, unless another variant has the same type:
+/// ```text
+/// impl From<T> for MyEnum
+/// impl TryFrom<MyEnum> for T
+/// ```
+///
+/// Generic enums only have these if a single variant has a value, and its type isn't just the type parameter, as instantiating them could give two variants the same type.
+///
+/// Records with the same Dhall type that were given different names (e.g. with `rust_struct`) convert into each other with `From`, if their fields have the same Rust types.
+///
+/// If struct functions are enabled (or struct fields are not `pub`), a field `my_field` of type `T` will have the following functions:
+/// ```text
 /// fn my_field(&self) -> &T
 /// fn my_field_mut(&mut self) -> &mut T
 /// ```
+///
+/// With `struct_constructors`, every struct has a `new` function taking its fields in alphabetical order, and a consuming setter for each field, which takes the value of `Option` fields:
+/// ```text
+/// fn new(my_field: T, my_option: Option<U>) -> Self
+/// fn with_my_field(self, value: T) -> Self
+/// fn with_my_option(self, value: U) -> Self
+/// ```
+/// A field named like one of these functions, such as `new`, or `with_x` next to a field `x` when accessors are generated, is an error,
+/// as is an accessor named like one of the loaders and writers below. `new` only allows Clippy's `too_many_arguments` lint when it takes more than 7 fields.
 /// 
+/// Every non-anonymous type has the following functions, which load a value checked against the type's Dhall type:
+/// ```text
+/// fn from_dhall_file(path: impl AsRef<Path>) -> serde_dhall::Result<Self>
+/// fn from_dhall_file_with_imports(path: impl AsRef<Path>, imports: bool) -> serde_dhall::Result<Self>
+/// fn from_dhall_str(source: &str) -> serde_dhall::Result<Self>
+/// ```
+/// If the value has the wrong type, the error starts with the path to the first mismatched value, e.g. `occupation.salary: expected Natural, found Text`, followed by serde_dhall's message.
+///
+/// Every type can also be written as Dhall annotated with its type (so unions, empty lists and `None` are rendered correctly), which the functions above can load again:
+/// ```text
+/// fn to_dhall_string(&self) -> serde_dhall::Result<String>
+/// fn write_dhall_file(&self, path: impl AsRef<Path>) -> std::io::Result<()>
+/// ```
+///
+/// # Static Types
+///
+/// Every generated type, except those referring to recursive types, implements `serde_dhall::StaticType`, returning the Dhall type it was generated from, so values can be loaded with `.static_type_annotation()`.
+/// Generic types implement it for any type parameter that implements `StaticType`, e.g. `Pair<u64>` has the type `{ first : Natural, second : Natural }`.
+///
+/// # Generated Documentation
+///
+/// Generated types have doc comments naming the .dhall file (and, for anonymous types, the field path) they were generated from, along with their Dhall type.
+/// Fields and variants are documented with their Dhall type, and fields of schema types also list their default value.
+///
+/// `--` and `{- -}` comments written directly above a record type, union type or the `let` binding one, above a field or alternative, or after a field or alternative on the same line, are added to the start of the matching doc comment.
+/// Comments are matched to generated types by their field or alternative names, looking in the .dhall file a type was generated from and then in the local files it imports.
+/// If the first file with a match has several types with those names and different comments, the comments of neither are used.
+///
 /// # Dhall Metadata
 /// 
 /// Because Dhall and Rust have fundamentally different type systems, it may be necessary to make some non-functional changes in the Dhall to generate Rust types as you intend.
@@ -282,6 +754,7 @@ fn set_environment_variable() {
 /// Rust types, however, as it creates ambiguities when determining which Rust type should be used for a given Dhall type.  
 /// To avoid the issue, you can assign 'name metadata' to your identical Dhall types so that they can be differentiated during evaluation. This will not affect your Dhall types in other
 /// contexts - the resultant Dhall types are only modified when specific environment variables are set.  
+/// When a type is ambiguous, the error lists every type with the same structure and suggests the metadata to add to each of them.  
 /// The first method can be used with either records or unions and precedes the type:
 /// 
 /// ### file_a.dhall
@@ -298,7 +771,7 @@ fn set_environment_variable() {
 /// 
 /// ... generates...
 /// 
-/// ``` rust
+/// ```rust
 ///     pub enum MyEnumA {
 ///       A,
 ///       B,
@@ -325,7 +798,7 @@ fn set_environment_variable() {
 ///
 /// ... generates...
 /// 
-/// ``` rust
+/// ```rust
 ///     pub struct MyStructA { 
 ///       pub name: String,
 ///     }
@@ -369,8 +842,8 @@ fn set_environment_variable() {
 /// ```
 ///
 /// ### ./src/dhall.rs
-/// ``` rust
-///     #[serde_dhall_typegen::dhall_types("./dhall/schema/")]
+/// ```rust
+///     #[serde_dhall_typegen::dhall_types("./tests/dhall/schema/")]
 ///     mod dhall { }
 /// ```
 ///
@@ -379,63 +852,93 @@ fn set_environment_variable() {
 /// ### ./src/dhall.rs (equivalent to)
 /// ```rust
 ///     mod dhall {
-///         #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize)]
+///         #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize, ::serde_dhall::StaticType)]
 ///         pub struct Person {
 ///             pub age_range: PersonAnon0,
 ///             pub name: String,
 ///             pub occupation: Option<PersonAnon1>,
 ///         }
 ///    
-///         #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize)]
+///         #[derive(Debug, Clone, Copy, PartialOrd, Ord, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize, ::serde_dhall::StaticType)]
 ///         pub enum PersonAnon0 {
-///             Teenager,
-///             Senior,
+///             Adult,
 ///             Baby,
-///             Toddler,
 ///             Child,
-///             Adult,
+///             Senior,
+///             Teenager,
+///             Toddler,
 ///         }
 ///    
 ///         impl PersonAnon0 {
-///             pub fn is_teenager(&self) -> bool {
+///             pub fn is_adult(&self) -> bool {
 ///                 match self {
-///                     Self::Teenager => true,
+///                     Self::Adult => true,
 ///                     _ => false,
 ///                 }
 ///             }
-///             pub fn is_senior(&self) -> bool {
+///             pub fn is_baby(&self) -> bool {
 ///                 match self {
-///                     Self::Senior => true,
+///                     Self::Baby => true,
 ///                     _ => false,
 ///                 }
 ///             }
-///             pub fn is_baby(&self) -> bool {
+///             pub fn is_child(&self) -> bool {
 ///                 match self {
-///                     Self::Baby => true,
+///                     Self::Child => true,
 ///                     _ => false,
 ///                 }
 ///             }
-///             pub fn is_toddler(&self) -> bool {
+///             pub fn is_senior(&self) -> bool {
 ///                 match self {
-///                     Self::Toddler => true,
+///                     Self::Senior => true,
 ///                     _ => false,
 ///                 }
 ///             }
-///             pub fn is_child(&self) -> bool {
+///             pub fn is_teenager(&self) -> bool {
 ///                 match self {
-///                     Self::Child => true,
+///                     Self::Teenager => true,
 ///                     _ => false,
 ///                 }
 ///             }
-///             pub fn is_adult(&self) -> bool {
+///             pub fn is_toddler(&self) -> bool {
 ///                 match self {
-///                     Self::Adult => true,
+///                     Self::Toddler => true,
 ///                     _ => false,
 ///                 }
 ///             }
 ///         }
+///
+///         impl PersonAnon0 {
+///             pub const ALL: [Self; 6] = [Self::Adult, Self::Baby, Self::Child, Self::Senior, Self::Teenager, Self::Toddler];
+///             pub const COUNT: usize = 6;
+///
+///             pub fn as_str(&self) -> &'static str {
+///                 match *self {
+///                     Self::Adult => "Adult",
+///                     Self::Baby => "Baby",
+///                     Self::Child => "Child",
+///                     Self::Senior => "Senior",
+///                     Self::Teenager => "Teenager",
+///                     Self::Toddler => "Toddler",
+///                 }
+///             }
+///         }
+///
+///         impl std::fmt::Display for PersonAnon0 {
+///             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///                 f.write_str(self.as_str())
+///             }
+///         }
+///
+///         impl std::str::FromStr for PersonAnon0 {
+///             type Err = String;
+///
+///             fn from_str(s: &str) -> Result<Self, Self::Err> {
+///                 Self::ALL.iter().copied().find(|variant| variant.as_str() == s).ok_or_else(|| format!("Unknown alternative '{}'", s))
+///             }
+///         }
 ///  
-///         #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize)]
+///         #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize, ::serde_dhall::StaticType)]
 ///         pub struct PersonAnon1 {
 ///             pub salary: u64,
 ///             pub title: String,
@@ -449,13 +952,19 @@ pub fn dhall_types(attr: proc_macro::TokenStream, item: proc_macro::TokenStream)
     let span = module.mod_token.span;
 
     let ts = match dhall_types_attribute.into_options() {
-        Ok(options) => {
+        Ok(mut options) => {
+            if let Some((_brace, items)) = &module.content {
+                options.hand_written_types = HandWrittenType::collect(items);
+            }
+            // Errors without a more specific span point at the path literal
+            let path_span = options.path.1;
             // Everything generated by dhall_types_internal should have the module's span, 
             // but avoid passing the span everywhere and just set it on the final TokenStream
-            let (Ok(ts) | Err(ts)) = dhall_types_internal(options);
-            quote_spanned!(span => #ts)
+            let (ts, errors) = dhall_types_internal(options);
+            let errors = errors.iter().map(|e| e.to_compile_error(path_span));
+            quote_spanned!(span => #ts #(#errors)*)
         }
-        Err(e) => e,
+        Err(e) => e.to_compile_error(span),
     };
     
     if let Some((_brace, items)) = &mut module.content {
@@ -466,7 +975,109 @@ pub fn dhall_types(attr: proc_macro::TokenStream, item: proc_macro::TokenStream)
     }.into()
 }
 
-fn try_parse_as_schema(root_dhall_path_str: &str, dhall_path_str: &str) -> Option<Schema> {
+/// Generate functions writing the Dhall type of a Rust struct or enum, the inverse of `dhall_types`.
+/// 
+/// Primitives, `Option`, `Vec` and a single type parameter are converted to their Dhall equivalents.
+/// Maps are rejected, as serde_dhall writes them as records whose fields aren't known up front, and `dhall_types` reads `toMap` lists back as lists of entries.
+/// Any other type must also derive `DhallSchema`, and is imported from its own file in the same directory.
+/// Fields and variants are named as serde serializes them, following `rename` and `rename_all`, and those with `#[serde(skip)]` or `#[serde(skip_serializing)]` are left out.
+/// With `#[dhall_schema(default)]`, a `{ Type, default }` schema is written using the type's `Default` impl,
+/// which requires the type to also implement `serde::Serialize` and `serde_dhall::StaticType`.
+/// 
+/// ```rust,no_run
+/// # fn main() -> std::io::Result<()> {
+/// #[derive(Default, serde::Serialize, serde_dhall::StaticType, serde_dhall_typegen::DhallSchema)]
+/// #[dhall_schema(default)]
+/// struct Settings {
+///     volume: u64,
+///     theme: Option<String>,
+/// }
+/// 
+/// // Writes `{ Type = { volume : Natural, theme : Optional Text }, default = { theme = None Text, volume = 0 } }` to ./dhall/settings.dhall
+/// Settings::write_dhall_schema("./dhall")?;
+/// # Ok(())
+/// # }
+/// ```
+/// 
+/// The following functions are generated:
+/// ```text
+/// fn dhall_type() -> String
+/// fn dhall_schema_file_name() -> &'static str
+/// fn dhall_type_import() -> String
+/// fn dhall_schema() -> serde_dhall::Result<String>
+/// fn write_dhall_schema(directory: impl AsRef<Path>) -> std::io::Result<()>
+/// ```
+#[proc_macro_derive(DhallSchema, attributes(dhall_schema))]
+pub fn derive_dhall_schema(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let span = input.ident.span();
+    dhall_schema::derive_dhall_schema(&input).unwrap_or_else(|e| e.to_compile_error(span)).into()
+}
+
+/// Check a hand-written struct or enum against the Dhall type in a .dhall file, without generating anything.
+/// 
+/// The path is relative to the crate root, like the path of `dhall_types`. The file may be a record or union type, or a `{ Type, default }` schema.
+/// Fields and variants are compared by the name serde gives them, and by type where the Rust type is a primitive, `Option`, `Vec`, `HashMap` or `BTreeMap`,
+/// the same check `dhall_types` makes for hand-written types in its module. Mismatches are compile errors.
+/// Unlike in `dhall_types`, the Rust types of the other types a field or variant refers to aren't known, so they are assumed to match.
+/// 
+/// ```rust
+/// # #[derive(serde::Deserialize)]
+/// # struct Color { r: u8, g: u8, b: u8 }
+/// # #[derive(serde::Deserialize)]
+/// # enum Mode { Light, Dark, Custom(String) }
+/// #[serde_dhall_typegen::dhall_type("./tests/dhall/hand_written/palette.dhall")]
+/// #[derive(serde::Deserialize)]
+/// struct Palette {
+///     name: String,
+///     primary: Color,
+///     #[serde(rename = "accents")]
+///     accent_colors: Vec<Color>,
+///     mode: Mode,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn dhall_type(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(attr as syn::LitStr);
+    let item = parse_macro_input!(item as syn::Item);
+    let errors = dhall_type_internal(&path.value(), &item).unwrap_or_else(Vec::from);
+    let errors = errors.iter().map(|e| e.to_compile_error(path.span()));
+    quote!(#item #(#errors)*).into()
+}
+
+fn dhall_type_internal(path: &str, item: &syn::Item) -> Result<Vec<Error>, Error> {
+    use path_slash::PathBufExt;
+    use syn::spanned::Spanned as _;
+
+    let hand_written = HandWrittenType::new(item)
+        .filter(HandWrittenType::is_checkable)
+        .ok_or_else(|| Error::new_spanned("Expected a struct with named fields, or an enum", item.span()))?;
+
+    let path = Path::new(path);
+    let file_name = path.file_name().ok_or_else(|| Error::new(format!("'{}' is not a .dhall file", path.display())))?;
+    let relative_path_str = Path::new(".").join(file_name).to_slash().ok_or_else(|| Error::new(format!("Invalid unicode in file '{}'", path.display())))?;
+    let root_dhall_path = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let root_dhall_path_str = root_dhall_path.as_os_str().to_string_lossy();
+    let origin = TypeOrigin::new(&relative_path_str);
+
+    set_environment_variable();
+    let ty = if let Some(schema) = try_parse_as_schema(&root_dhall_path_str, &relative_path_str)? {
+        schema.r#type
+    } else if let Some(ty) = try_parse_as_aggregate_type(&root_dhall_path_str, &relative_path_str)? {
+        ty
+    } else {
+        let _cd = util::PushCd::new(root_dhall_path).map_err(Error::new)?;
+        let deser = serde_dhall::from_file(relative_path_str.as_str());
+        return Err(invalid_type_error(&relative_path_str, deser.parse::<SimpleType>().err()));
+    };
+    let info = AggregateType::try_new(&ty).and_then(AggregateType::info).map_err(|e| e.in_origin(&origin))?;
+    Ok(hand_written.check(info, &origin, None))
+}
+
+fn try_parse_as_schema(root_dhall_path_str: &str, dhall_path_str: &str) -> Result<Option<Schema>, Error> {
     let str_type = format!("\
         let schema = {}
         in
@@ -481,28 +1092,44 @@ fn try_parse_as_schema(root_dhall_path_str: &str, dhall_path_str: &str) -> Optio
         dhall_path_str
     );
 
-    let _cd = util::PushCd::new(Path::new(root_dhall_path_str)).unwrap();
+    let _cd = util::PushCd::new(Path::new(root_dhall_path_str)).map_err(Error::new)?;
     let deser_type = serde_dhall::from_str(str_type.as_str());
     let deser_default = serde_dhall::from_str(str_default.as_str());
     
-    let schema_type = deser_type.parse().ok()?;
-    let schema_default = deser_default.parse().ok()?;
+    let schema_type = match deser_type.parse() {
+        Ok(schema_type) => schema_type,
+        Err(_) => return Ok(None),
+    };
+    let schema_default = match deser_default.parse() {
+        Ok(schema_default) => schema_default,
+        Err(_) => return Ok(None),
+    };
 
     let schema = Schema { r#type: schema_type, default: schema_default };
-    Some(schema)
+    Ok(Some(schema))
 }
 
-fn try_parse_as_aggregate_type(root_dhall_path_str: &str, dhall_path_str: &str) -> Option<SimpleType> {
-    let _cd = util::PushCd::new(Path::new(root_dhall_path_str)).unwrap();
+fn try_parse_as_aggregate_type(root_dhall_path_str: &str, dhall_path_str: &str) -> Result<Option<SimpleType>, Error> {
+    let _cd = util::PushCd::new(Path::new(root_dhall_path_str)).map_err(Error::new)?;
     let deser = serde_dhall::from_file(dhall_path_str);
-    deser.parse().ok()
+    Ok(deser.parse().ok())
 }
 
 fn new_generic_sentinel() -> SimpleType {
     SimpleType::Union(HashMap::from([("__sentinel".to_string(), None)]))
 }
 
-fn try_parse_as_open_type(root_dhall_path_str: &str, dhall_path_str: &str) -> Option<SimpleType> {
+/// Mark the type parameter of a generic record that doesn't use it, as if with `env:rust_phantom`, so it still becomes a generic type
+fn mark_phantom_parameter(ty: &mut SimpleType) {
+    if let SimpleType::Record(r) = ty {
+        match r.get_mut(META_FIELD_PREFIX_CONTENT) {
+            Some(content) => mark_phantom_parameter(content),
+            None => { r.insert(META_FIELD_PHANTOM.to_string(), new_generic_sentinel()); },
+        }
+    }
+}
+
+fn try_parse_as_open_type(root_dhall_path_str: &str, dhall_path_str: &str) -> Result<Option<SimpleType>, Error> {
     let str_type = format!("\
         let func = {}
         in
@@ -510,12 +1137,66 @@ fn try_parse_as_open_type(root_dhall_path_str: &str, dhall_path_str: &str) -> Op
         dhall_path_str,
         new_generic_sentinel()
     );
-    let _cd = util::PushCd::new(Path::new(root_dhall_path_str)).unwrap();
+    let _cd = util::PushCd::new(Path::new(root_dhall_path_str)).map_err(Error::new)?;
     let deser = serde_dhall::from_str(&str_type);
-    deser.parse().ok()
+    Ok(deser.parse().ok())
+}
+
+fn invalid_type_error(path: &str, err: Option<serde_dhall::Error>) -> Error {
+    Error::new(match err {
+        Some(err) => format!("Dhall file '{}' is not a type, schema, or a function returning a type:\n{}", path, err),
+        None => format!("Dhall file '{}' is not a type, schema, or a function returning a type", path),
+    })
+}
+
+fn add_dhall_file<'a>(typegen: &mut TypeGen<'a>, ty_storage: &'a AppendList<SimpleType>, dhall_path: &Path, file_path: &Path) -> Result<(), Error> {
+    use path_slash::PathBufExt;
+
+    let type_str = file_path.file_stem().ok_or_else(|| Error::new(format!("No file name for '{}'", file_path.display())))?.to_str().ok_or_else(|| Error::new(format!("Invalid file name '{}'", file_path.display())))?.to_case(Case::Pascal);
+    
+    let relative_path = Path::new(".").join(pathdiff::diff_paths(file_path, dhall_path).ok_or_else(|| Error::new(format!("Unable to create relative path for file '{}'", file_path.display())))?);
+    
+    let relative_path_str = relative_path.to_slash().ok_or_else(|| Error::new(format!("Invalid unicode in file '{}'", file_path.display())))?;
+    let origin = TypeOrigin::new(&relative_path_str);
+
+    // Comments are optional, so a file that can't be read is reported by the Dhall evaluation below instead
+    if let Ok(source) = std::fs::read_to_string(file_path) {
+        typegen.add_source_docs(&origin, &source);
+    }
+
+    let root_dhall_path_str = dhall_path.as_os_str().to_string_lossy();
+    let (ty, schema_default) = if let Some(schema) = try_parse_as_schema(&root_dhall_path_str, &relative_path_str)? {
+        (schema.r#type, Some(schema.default))
+    } else if let Some(ty) = try_parse_as_aggregate_type(&root_dhall_path_str, &relative_path_str)? {
+        (ty, None)
+    } else if let Some(mut ty) = try_parse_as_open_type(&root_dhall_path_str, &relative_path_str)? {
+        if !simple_type_is_open_generic(&ty) {
+            mark_phantom_parameter(&mut ty);
+        }
+        (ty, None)
+    } else {
+        let _cd = util::PushCd::new(Path::new(root_dhall_path_str.as_ref())).map_err(Error::new)?;
+        let deser = serde_dhall::from_file(relative_path_str.as_str());
+        return Err(invalid_type_error(&relative_path_str, deser.parse::<SimpleType>().err()));
+    };
+
+    ty_storage.push(ty);
+    let last_ty_index = ty_storage.len() - 1;
+    let ty = &ty_storage[last_ty_index];
+
+    let ident = Ident::new(&type_str, Span::call_site());
+    // Files evaluating to any other type (e.g. `Natural` or `List Text`) are generated as type aliases
+    if let Some((name, content)) = alias_metadata(ty).map_err(|e| e.in_origin(&origin))? {
+        typegen.add_alias(Ident::new(name, Span::call_site()), content, origin)
+    } else if AggregateType::try_new(ty).is_ok() && pointer_metadata(ty).map_err(|e| e.in_origin(&origin))?.is_none() {
+        typegen.add_type(ty, ident, origin, schema_default)
+    } else {
+        typegen.add_alias(ident, ty, origin)
+    }
 }
 
-fn dhall_types_internal(options: Options) -> Result<TokenStream, TokenStream> {
+/// Generate the types for every valid .dhall file, along with the errors for all invalid ones
+fn dhall_types_internal(options: Options) -> (TokenStream, Vec<Error>) {
     let dhall_path = path::Path::new(&options.path.0).to_path_buf();
 
     set_environment_variable();
@@ -529,45 +1210,42 @@ fn dhall_types_internal(options: Options) -> Result<TokenStream, TokenStream> {
 
     let mut typegen = TypeGen::new(&primitives, options);
 
-    while !directory_stack.is_empty() {
-        let current_path = directory_stack.pop().unwrap();
-        for entry in current_path.read_dir().map_err(|e| create_error(&format!("Directory read error: {}", e)))? {
-            let entry = entry.map_err(|e| create_error(&format!("File read error: {}", e)))?;
-            let entry_metadata = entry.metadata().map_err(|e| create_error(&e.to_string()))?;
+    let mut errors = Vec::new();
+
+    while let Some(current_path) = directory_stack.pop() {
+        let entries = match current_path.read_dir() {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push(Error::new(format!("Directory read error for '{}': {}", current_path.display(), e)));
+                continue;
+            }
+        };
+
+        // Visit the files in a fixed order, so errors are reported in the same order on every platform
+        let mut entries: Vec<_> = entries.collect();
+        entries.sort_by_key(|entry| entry.as_ref().ok().map(std::fs::DirEntry::path));
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    errors.push(Error::new(format!("File read error: {}", e)));
+                    continue;
+                }
+            };
+            let entry_metadata = match entry.metadata() {
+                Ok(entry_metadata) => entry_metadata,
+                Err(e) => {
+                    errors.push(Error::new(format!("File read error for '{}': {}", entry.path().display(), e)));
+                    continue;
+                }
+            };
             if entry_metadata.is_file() {
                 if let Some(ext) = entry.path().extension() {
                     if ext.to_ascii_lowercase() == ffi::OsStr::new("dhall") {
-                        use path_slash::PathBufExt;
-
-                        fn invalid_type_error(path: &str, err: Option<serde_dhall::Error>) -> Result<TokenStream, TokenStream> {
-                            Err(create_error(&format!("Dhall type in file '{}' is not a Record, Union, or schema: {:?}", path, err)))
+                        if let Err(e) = add_dhall_file(&mut typegen, &ty_storage, &dhall_path, &entry.path()) {
+                            errors.push(e);
                         }
-
-                        let type_str = entry.path().file_stem().ok_or_else(|| create_error("No file name"))?.to_str().ok_or_else(|| create_error("Invalid file name"))?.to_case(Case::Pascal);
-                        
-                        let relative_path = Path::new(".").join(pathdiff::diff_paths(entry.path(), &dhall_path).ok_or_else(|| create_error(&format!("Unable to create relative path for file '{}'", entry.path().display())))?);
-                        
-                        let relative_path_str = relative_path.to_slash().ok_or_else(|| create_error(&format!("Invalid unicode in file '{}'", entry.path().display())))?;
-
-                        let root_dhall_path_str = dhall_path.as_os_str().to_string_lossy();
-                        let ty = if let Some(schema) = try_parse_as_schema(&root_dhall_path_str, &relative_path_str) {
-                            schema.r#type
-                        } else if let Some(ty) = try_parse_as_aggregate_type(&root_dhall_path_str, &relative_path_str) {
-                            ty
-                        } else if let Some(ty) = try_parse_as_open_type(&root_dhall_path_str, &relative_path_str) {
-                            ty
-                        } else {
-                            let _cd = util::PushCd::new(Path::new(root_dhall_path_str.as_ref())).unwrap();
-                            let deser = serde_dhall::from_file(relative_path_str.as_str());
-                            return invalid_type_error(&relative_path_str, deser.parse::<SimpleType>().err());
-                        };
-
-                        ty_storage.push(ty);
-                        let last_ty_index = ty_storage.len() - 1;
-                        let ty = AggregateType::try_new(&ty_storage[last_ty_index]).map_err(|_| create_error(&format!("'{}' does not contain a record, union, or a function returning one", relative_path_str)))?;
-
-                        let ident = Ident::new(&type_str, Span::call_site());
-                        typegen.add_type(ty, ident)?;
                     }
                 }
             } else if entry_metadata.is_dir() {
@@ -578,5 +1256,73 @@ fn dhall_types_internal(options: Options) -> Result<TokenStream, TokenStream> {
         }
     }
 
-    typegen.tokenize()
+    let (tokens, tokenize_errors) = typegen.tokenize();
+    errors.extend(tokenize_errors);
+    (tokens, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generating changes the current directory, so only one test can generate at a time
+    static GENERATE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// The generated code for the types in `directory` with the `dhall_types` options in `options`, which must have no errors
+    fn generate(directory: &str, options: &str) -> String {
+        let _lock = GENERATE_LOCK.lock().unwrap();
+        let attribute: DhallTypesAttribute = syn::parse_str(&format!("{:?}{}", directory, options)).unwrap();
+        let (tokens, errors) = dhall_types_internal(attribute.into_options().unwrap());
+        assert!(errors.is_empty(), "{:?}", errors);
+        tokens.to_string()
+    }
+
+    fn doc(lines: &[&str]) -> String {
+        quote!(#(#[doc = #lines])*).to_string()
+    }
+
+    #[test]
+    fn origin_docs() {
+        let tokens = generate("tests/dhall/schema/", "");
+        for lines in [
+            // Named types name their file, and anonymous types the field path within it
+            &[" Generated from `person.dhall`.", "", " Dhall type: `{ age_range : < Adult | Baby | Child | Senior | Teenager | Toddler >, name : Text, occupation : Optional { salary : Natural, title : Text } }`"][..],
+            &[" Generated from `age_range` in `person.dhall`.", "", " Dhall type: `< Adult | Baby | Child | Senior | Teenager | Toddler >`"],
+            &[" Generated from `occupation` in `person.dhall`.", "", " Dhall type: `{ salary : Natural, title : Text }`"],
+            &[" Generated from `buttons` in `widget.dhall`.", "", " Dhall type: `{ color : < Blue | Green | Red >, pressed : Bool }`"],
+            &[" Generated from `quantity.dhall`.", "", " Dhall type: `\\(T : Type) -> { item : T, quantity : Natural }`"],
+            // Fields have their Dhall types, and fields of schemas their defaults
+            &[" Dhall type: `Optional { salary : Natural, title : Text }`", "", " Default: `None { salary : Natural, title : Text }`"],
+            &[" Dhall type: `List { item : Text, quantity : Natural }`", "", " Default: `[] : List { item : Text, quantity : Natural }`"],
+        ] {
+            assert!(tokens.contains(&doc(lines)), "Missing doc lines {:?} in:\n{}", lines, tokens);
+        }
+    }
+
+    #[test]
+    fn comment_docs() {
+        let tokens = generate("tests/dhall/docs/", "");
+        for lines in [
+            // Comments come before the generated docs, separated by a blank line
+            &[" Someone working for the company", "", " Generated from `employee.dhall`."][..],
+            &[" Their full name", "", " Dhall type: `Text`"],
+            &[" Leads other engineers", "", " Dhall alternative without a value."],
+            &[" Sets technical direction", "", " Dhall alternative without a value."],
+            &[" The manager's full name", "", " Dhall type: `Text`"],
+            &[" How many people", " report to them", "", " Dhall type: `Natural`"],
+            &[" A group of employees,", " defined in another file", "", " Generated from `team.dhall`."],
+            &[" Where the team works", "", " Dhall type: `Text`"],
+        ] {
+            assert!(tokens.contains(&doc(lines)), "Missing doc lines {:?} in:\n{}", lines, tokens);
+        }
+    }
+
+    #[test]
+    fn constructor_arguments() {
+        // Only constructors with more arguments than Clippy allows get its `allow`
+        let tokens = generate("tests/dhall/constructor_arguments/", ", struct_constructors = true");
+        let allow = quote!(#[allow(clippy::too_many_arguments)]).to_string();
+        assert_eq!(tokens.matches(&allow).count(), 1, "{}", tokens);
+        assert!(tokens.contains(&format!("{} pub fn new (a : u64", allow)), "{}", tokens);
+    }
 }
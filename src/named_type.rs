@@ -7,6 +7,8 @@ use quote::ToTokens;
 use serde_dhall::SimpleType;
 
 use crate::AggregateType;
+use crate::options::MappedType;
+use crate::type_origin::TypeOrigin;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct IndexedIdent<'a> {
@@ -58,26 +60,51 @@ impl<'a> fmt::Display for IndexedIdent<'a> {
     }
 }
 
+/// An element of the chain naming a Rust type (e.g. `Option` then `u64`), see `TypeCollection::get_idents`
+#[derive(Debug, Clone, Copy)]
+pub enum TypeName<'a> {
+    Ident(IndexedIdent<'a>),
+    // An existing Rust type given with the `type_map` option, in place of a generated type
+    Mapped(&'a MappedType),
+}
+
+impl<'a> From<IndexedIdent<'a>> for TypeName<'a> {
+    fn from(ident: IndexedIdent<'a>) -> Self {
+        Self::Ident(ident)
+    }
+}
+
+impl<'a> ToTokens for TypeName<'a> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Self::Ident(ident) => ident.to_tokens(tokens),
+            Self::Mapped(mapped) => mapped.ty.to_tokens(tokens),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct NamedType<'a, 'i> {
     ident: IndexedIdent<'i>,
     aggregate_type: AggregateType<'a>,
+    origin: &'i TypeOrigin,
 }
 
 impl<'a, 'i> NamedType<'a, 'i> {
-    pub fn new(ident: IndexedIdent<'i>, aggregate_type: AggregateType<'a>) -> Self {
+    pub fn new(ident: IndexedIdent<'i>, aggregate_type: AggregateType<'a>, origin: &'i TypeOrigin) -> Self {
         Self {
             ident,
             aggregate_type,
+            origin,
         }
     }
 
-    pub fn new_named(ident: &'i Ident, aggregate_type: AggregateType<'a>) -> Self {
-        Self::new(IndexedIdent::new_named(ident), aggregate_type)
+    pub fn new_named(ident: &'i Ident, aggregate_type: AggregateType<'a>, origin: &'i TypeOrigin) -> Self {
+        Self::new(IndexedIdent::new_named(ident), aggregate_type, origin)
     }
 
-    pub fn new_anonymous(ident: &'i Ident, index: usize, aggregate_type: AggregateType<'a>) -> Self {
-        Self::new(IndexedIdent::new_anonymous(ident, index), aggregate_type)
+    pub fn new_anonymous(ident: &'i Ident, index: usize, aggregate_type: AggregateType<'a>, origin: &'i TypeOrigin) -> Self {
+        Self::new(IndexedIdent::new_anonymous(ident, index), aggregate_type, origin)
     }
 
     pub fn ident(&self) -> IndexedIdent<'i> {
@@ -87,6 +114,11 @@ impl<'a, 'i> NamedType<'a, 'i> {
     pub fn ty(&self) -> AggregateType<'a> {
         self.aggregate_type
     }  
+
+    /// Where the type was first encountered in the Dhall source
+    pub fn origin(&self) -> &'i TypeOrigin {
+        self.origin
+    }
 }
 
 pub struct ClosedNamedType<'a, 'i, 'p> {
@@ -106,6 +138,10 @@ impl<'a, 'i, 'p> ClosedNamedType<'a, 'i, 'p> {
         self.ty.ident
     }
 
+    pub fn named_type(&self) -> NamedType<'a, 'i> {
+        self.ty
+    }
+
     #[allow(dead_code)]
     pub fn ty(&self) -> AggregateType<'a> {
         self.ty.aggregate_type
@@ -115,3 +151,41 @@ impl<'a, 'i, 'p> ClosedNamedType<'a, 'i, 'p> {
         self.type_parameter
     }
 }
+
+/// A type that is not a record or union (e.g. `Natural` or `List Text`) given a name by its file or metadata,
+/// or an instantiation of a generic type (e.g. `Quantity Text`) given a name by its file
+#[derive(Debug)]
+pub struct TypeAlias<'a> {
+    ident: Ident,
+    ty: &'a SimpleType,
+    origin: TypeOrigin,
+}
+
+impl<'a> TypeAlias<'a> {
+    pub fn new(ident: Ident, ty: &'a SimpleType, origin: TypeOrigin) -> Self {
+        Self {
+            ident,
+            ty,
+            origin,
+        }
+    }
+
+    pub fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    /// The aliased type, without metadata
+    pub fn ty(&self) -> &'a SimpleType {
+        self.ty
+    }
+
+    /// The aliased record or union, if this is an alias of an instantiation of a generic type
+    pub fn instantiation(&self) -> Option<AggregateType<'a>> {
+        AggregateType::try_new(self.ty).ok()
+    }
+
+    /// Where the alias was first encountered in the Dhall source
+    pub fn origin(&self) -> &TypeOrigin {
+        &self.origin
+    }
+}
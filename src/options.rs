@@ -1,8 +1,11 @@
 use std::path;
+use std::str::FromStr;
 
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
 
 use crate::Spanned;
+use crate::hand_written::HandWrittenType;
 
 #[derive(Debug)]
 pub(crate) struct Options {
@@ -11,6 +14,25 @@ pub(crate) struct Options {
     pub named_enum_impl: Spanned<bool>,
     pub anonymous_struct_impl: Spanned<bool>,
     pub named_struct_impl: Spanned<bool>,
+    pub type_visibility: Spanned<Visibility>,
+    pub field_visibility: Spanned<Visibility>,
+    pub inline_variant_records: Spanned<bool>,
+    pub alias_newtypes: Spanned<bool>,
+    // Type names and `Type.member` paths whose references or members are wrapped in a pointer
+    pub pointers: Vec<(String, Spanned<Pointer>)>,
+    // Box union alternative payloads estimated to be larger than this many bytes
+    pub box_variants_over: Spanned<Option<u64>>,
+    // Type names and `Type.member` paths that are existing Rust types rather than generated ones
+    pub type_map: Vec<(String, Spanned<MappedType>)>,
+    // Structs and enums written in the module, used instead of generating the types of the same name
+    pub hand_written_types: Vec<HandWrittenType>,
+    pub check_hand_written_types: Spanned<bool>,
+    // Add a catch-all variant to every generated enum, or only to the enums named in `catch_all_types`
+    pub catch_all_variants: Spanned<bool>,
+    pub catch_all_types: Vec<Spanned<String>>,
+    // Generate a nullary `<Enum>Kind` enum naming the alternatives of every enum with values
+    pub kind_enums: Spanned<bool>,
+    pub struct_constructors: Spanned<bool>,
 }
 
 impl Options {
@@ -21,22 +43,140 @@ impl Options {
             named_enum_impl: Spanned::new(false),
             anonymous_struct_impl: Spanned::new(false),
             named_struct_impl: Spanned::new(false),
+            type_visibility: Spanned::new(Visibility::Public),
+            field_visibility: Spanned::new(Visibility::Public),
+            inline_variant_records: Spanned::new(false),
+            alias_newtypes: Spanned::new(false),
+            pointers: Vec::new(),
+            box_variants_over: Spanned::new(None),
+            type_map: Vec::new(),
+            hand_written_types: Vec::new(),
+            check_hand_written_types: Spanned::new(true),
+            catch_all_variants: Spanned::new(false),
+            catch_all_types: Vec::new(),
+            kind_enums: Spanned::new(false),
+            struct_constructors: Spanned::new(false),
         }
     }
 
-    pub fn enum_impl(&self, is_anonymous: bool) -> &Spanned<bool> {
+    /// The pointer a type name or `Type.member` path was given with the `boxed`, `rc` or `arc` options
+    pub fn pointer(&self, path: &str) -> Option<Pointer> {
+        self.pointers.iter()
+            .find(|(pointer_path, _)| pointer_path == path)
+            .map(|(_, pointer)| pointer.value)
+    }
+
+    /// The Rust type a type name or `Type.member` path was given with the `type_map` option
+    pub fn mapped_type(&self, path: &str) -> Option<&MappedType> {
+        self.type_map.iter()
+            .find(|(mapped_path, _)| mapped_path == path)
+            .map(|(_, mapped)| &mapped.value)
+    }
+
+    /// The struct or enum named `ident` written in the module, if any
+    pub fn hand_written_type(&self, ident: &str) -> Option<&HandWrittenType> {
+        self.hand_written_types.iter().find(|hand_written| hand_written.ident() == ident)
+    }
+
+    /// Whether the enum named `type_name` (`None` for anonymous enums) gets a catch-all variant for alternatives its Dhall type doesn't have
+    pub fn catch_all(&self, type_name: Option<&str>) -> bool {
+        self.catch_all_variants.value || type_name.is_some_and(|type_name| self.catch_all_types.iter().any(|name| name.value == type_name))
+    }
+
+    pub fn enum_impl(&self, is_anonymous: bool) -> bool {
         if is_anonymous {
-            &self.anonymous_enum_impl
+            self.anonymous_enum_impl.value
         } else {
-            &self.named_enum_impl
+            self.named_enum_impl.value
         }
     }
 
-    pub fn struct_impl(&self, is_anonymous: bool) -> &Spanned<bool> {
-        if is_anonymous {
+    pub fn struct_impl(&self, is_anonymous: bool) -> bool {
+        let struct_impl = if is_anonymous {
             &self.anonymous_struct_impl
         } else {
             &self.named_struct_impl
+        };
+
+        // Non-public fields are only usable through accessors, so generate them unless told not to
+        if struct_impl.is_explicit() {
+            struct_impl.value
+        } else {
+            struct_impl.value || self.field_visibility.value != Visibility::Public
+        }
+    }
+}
+
+/// The visibility of generated types or fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Visibility {
+    Public,
+    Crate,
+    Super,
+    Private,
+}
+
+impl FromStr for Visibility {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        match s.as_str() {
+            "pub" => Ok(Self::Public),
+            "pub(crate)" => Ok(Self::Crate),
+            "pub(super)" => Ok(Self::Super),
+            "private" | "" => Ok(Self::Private),
+            _ => Err(()),
         }
     }
-}
\ No newline at end of file
+}
+
+impl ToTokens for Visibility {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Self::Public => quote!(pub),
+            Self::Crate => quote!(pub(crate)),
+            Self::Super => quote!(pub(super)),
+            Self::Private => TokenStream::new(),
+        })
+    }
+}
+
+/// A smart pointer wrapping a generated field, union alternative payload, or every reference to a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Pointer {
+    Box,
+    Rc,
+    Arc,
+}
+
+impl FromStr for Pointer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Box" => Ok(Self::Box),
+            "Rc" => Ok(Self::Rc),
+            "Arc" => Ok(Self::Arc),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ToTokens for Pointer {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(match self {
+            Self::Box => quote!(::std::boxed::Box),
+            Self::Rc => quote!(::std::rc::Rc),
+            Self::Arc => quote!(::std::sync::Arc),
+        })
+    }
+}
+
+/// An existing Rust type used in place of a generated type or member, given with the `type_map` option
+#[derive(Debug, Clone)]
+pub(crate) struct MappedType {
+    pub ty: TokenStream,
+    // The module whose `serialize` and `deserialize` functions convert between the Rust type and the Dhall value, for `#[serde(with = "...")]`
+    pub with: Option<String>,
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+
+/// The comments attached to a record or union type in Dhall source
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TypeDocs {
+    /// Comments directly preceding the type, e.g. above `let Person =`
+    pub type_doc: Vec<String>,
+    /// Comments preceding (or trailing on the same line as) each field or alternative
+    pub member_docs: HashMap<String, Vec<String>>,
+}
+
+impl TypeDocs {
+    pub fn is_empty(&self) -> bool {
+        self.type_doc.is_empty() && self.member_docs.is_empty()
+    }
+
+    pub fn member_doc(&self, member_name: &str) -> &[String] {
+        self.member_docs.get(member_name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// `--` and `{- -}` comments collected from the original source of .dhall files, since evaluating a file discards them.
+/// Comments are attached to the record and union types written in the source, which are matched to evaluated types by their member names
+/// within the file a type comes from and the files it imports.
+#[derive(Debug, Default)]
+pub struct SourceDocs {
+    // (file, sorted member names, docs)
+    types: Vec<(String, Vec<String>, TypeDocs)>,
+    // The local files each file imports
+    imports: HashMap<String, Vec<String>>,
+}
+
+impl SourceDocs {
+    pub fn add_file(&mut self, file: &str, source: &str) {
+        let tokens = tokenize(source);
+        for (mut member_names, docs) in parse(&tokens) {
+            member_names.sort();
+            self.types.push((file.to_owned(), member_names, docs));
+        }
+        let imports = tokens.iter().filter_map(|token| match token.kind {
+            TokenKind::Import(import) => Some(resolve_import(file, import)),
+            _ => None,
+        }).collect();
+        self.imports.insert(file.to_owned(), imports);
+    }
+
+    /// Find the docs for a type with the given members written in `file`, or else in the files it (indirectly) imports.
+    /// If the first file with such a type has several of them with different docs, it's unknown which one is meant, so there are none.
+    pub fn get<'s>(&self, file: &str, member_names: impl IntoIterator<Item=&'s str>) -> Option<&TypeDocs> {
+        let mut member_names: Vec<&str> = member_names.into_iter().collect();
+        member_names.sort_unstable();
+        let mut files = vec![file];
+        let mut index = 0;
+        while let Some(&file) = files.get(index) {
+            let mut docs = self.types.iter()
+                .filter(|(type_file, names, _)| type_file == file && names.iter().map(String::as_str).eq(member_names.iter().copied()))
+                .map(|(_, _, docs)| docs);
+            if let Some(first_docs) = docs.next() {
+                return Some(first_docs).filter(|first_docs| !first_docs.is_empty() && docs.all(|docs| docs == *first_docs));
+            }
+            for import in self.imports.get(file).into_iter().flatten() {
+                if !files.contains(&import.as_str()) {
+                    files.push(import);
+                }
+            }
+            index += 1;
+        }
+        None
+    }
+}
+
+/// The path of a local `import` relative to the Dhall directory, given the `file` it is written in
+fn resolve_import(file: &str, import: &str) -> String {
+    let mut parts: Vec<&str> = file.split('/').collect();
+    parts.pop();
+    for part in import.split('/') {
+        match part {
+            "" | "." => {},
+            ".." => { parts.pop(); },
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind<'s> {
+    Comment(Vec<String>),
+    Open(char),
+    Close(char),
+    Word(&'s str),
+    Punct(&'s str),
+    // A relative file import, e.g. `./person.dhall`
+    Import(&'s str),
+    // Literals, other imports and operators, none of which can be followed by a documented type
+    Other,
+}
+
+#[derive(Debug)]
+struct Token<'s> {
+    kind: TokenKind<'s>,
+    line: usize,
+}
+
+fn line_comment_text(text: &str) -> String {
+    text.strip_prefix(' ').unwrap_or(text).trim_end().to_owned()
+}
+
+fn block_comment_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    if let Some(first) = lines.first_mut() {
+        *first = first.trim_start();
+    }
+    // Counted in chars, as the leading whitespace may be multi-byte
+    let indent = lines.iter().skip(1).filter(|line| !line.is_empty()).map(|line| line.chars().take_while(|c| c.is_whitespace()).count()).min().unwrap_or(0);
+    let mut lines: Vec<String> = lines.iter().enumerate().map(|(index, line)| if index == 0 || line.is_empty() { line.to_string() } else { line.chars().skip(indent).collect() }).collect();
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    while lines.first().is_some_and(String::is_empty) {
+        lines.remove(0);
+    }
+    lines
+}
+
+/// The length of a nested `{- -}` comment at the start of `text`
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with("{-") {
+            depth += 1;
+            index += 2;
+        } else if rest.starts_with("-}") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return index;
+            }
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+/// The length of a `''` string at the start of `text`
+fn multiline_string_len(text: &str) -> usize {
+    let mut index = 2;
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with("'''") {
+            index += 3;
+        } else if rest.starts_with("''${") {
+            index += 4;
+        } else if rest.starts_with("''") {
+            return index + 2;
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+/// The length of a `"` string at the start of `text`
+fn string_len(text: &str) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => { chars.next(); },
+            '"' => return index + 1,
+            _ => {},
+        }
+    }
+    text.len()
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+fn is_import_char(c: char) -> bool {
+    !c.is_whitespace() && !"(){}[]<>,".contains(c)
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut line = 0;
+    let mut index = 0;
+    while let Some(c) = source[index..].chars().next() {
+        let rest = &source[index..];
+        let next = rest[c.len_utf8()..].chars().next();
+        let (kind, len) = if c.is_whitespace() {
+            (None, c.len_utf8())
+        } else if rest.starts_with("--") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            (Some(TokenKind::Comment(vec![line_comment_text(&rest[2..len])])), len)
+        } else if rest.starts_with("{-") {
+            let len = block_comment_len(rest);
+            let end = if rest[..len].ends_with("-}") { len - 2 } else { len };
+            (Some(TokenKind::Comment(block_comment_lines(&rest[2..end]))), len)
+        } else if rest.starts_with("''") {
+            (Some(TokenKind::Other), multiline_string_len(rest))
+        } else if c == '"' {
+            (Some(TokenKind::Other), string_len(rest))
+        } else if c == '`' {
+            let len = rest[1..].find('`').map_or(rest.len(), |end| end + 2);
+            (Some(TokenKind::Word(rest[1..len].trim_end_matches('`'))), len)
+        } else if rest.starts_with("->") || c == '→' {
+            (Some(TokenKind::Punct("->")), if c == '→' { c.len_utf8() } else { 2 })
+        } else if c == '\\' && next != Some('\\') || c == 'λ' {
+            (Some(TokenKind::Punct("\\")), c.len_utf8())
+        } else if rest.starts_with("./") || rest.starts_with("../") {
+            let len = rest.find(|c| !is_import_char(c)).unwrap_or(rest.len());
+            (Some(TokenKind::Import(&rest[..len])), len)
+        } else if rest.starts_with("~/") || rest.starts_with("env:") || rest.starts_with("http://") || rest.starts_with("https://")
+            || c == '/' && next.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            (Some(TokenKind::Other), rest.find(|c| !is_import_char(c)).unwrap_or(rest.len()))
+        } else if c.is_ascii_digit() || (c == '-' || c == '+') && next.is_some_and(|c| c.is_ascii_digit()) {
+            (Some(TokenKind::Other), rest[1..].find(|c: char| !c.is_ascii_alphanumeric() && c != '.').map_or(rest.len(), |end| end + 1))
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest.find(|c| !is_label_char(c)).unwrap_or(rest.len());
+            (Some(TokenKind::Word(&rest[..len])), len)
+        } else if "{<([".contains(c) {
+            (Some(TokenKind::Open(c)), 1)
+        } else if "}>)]".contains(c) {
+            (Some(TokenKind::Close(c)), 1)
+        } else if c == ',' {
+            (Some(TokenKind::Punct(",")), 1)
+        } else if ":=|".contains(c) && !next.is_some_and(|next| "/\\|&=!+*#?:".contains(next)) {
+            (Some(TokenKind::Punct(&rest[..1])), 1)
+        } else {
+            let len = rest.find(|c: char| !"/\\|&=!+*#?:".contains(c)).filter(|&len| len > 0).unwrap_or(c.len_utf8());
+            (Some(TokenKind::Other), len)
+        };
+
+        if let Some(kind) = kind {
+            tokens.push(Token { kind, line });
+        }
+        line += rest[..len].matches('\n').count();
+        index += len;
+    }
+    tokens
+}
+
+#[derive(Debug, Default)]
+struct Frame {
+    open: char,
+    // Comments not yet attached to a member or type
+    pending: Vec<String>,
+    type_doc: Vec<String>,
+    members: Vec<(String, Vec<String>)>,
+    // The line of the label of the last member, which trailing comments on that line attach to
+    member_line: Option<usize>,
+    expect_label: bool,
+    // Whether any member is a record literal field (`=`) rather than a record type field (`:`)
+    is_value: bool,
+}
+
+impl Frame {
+    fn new(open: char, type_doc: Vec<String>) -> Self {
+        Self {
+            open,
+            type_doc,
+            expect_label: open == '{' || open == '<',
+            ..Default::default()
+        }
+    }
+}
+
+/// Find every record or union type in `tokens` along with its member names and docs
+fn parse(tokens: &[Token<'_>]) -> Vec<(Vec<String>, TypeDocs)> {
+    let mut types = Vec::new();
+    let mut stack = vec![Frame::new('(', Vec::new())];
+    let mut last_code: Option<&Token> = None;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let next_code = tokens[index + 1..].iter().find(|token| !matches!(token.kind, TokenKind::Comment(_))).map(|token| &token.kind);
+        let frame = stack.last_mut().expect("The root frame is never popped");
+        match &token.kind {
+            TokenKind::Comment(lines) => {
+                let trailing = last_code.is_some_and(|last_code| last_code.line == token.line
+                    && !matches!(last_code.kind, TokenKind::Open(_))
+                    && (last_code.kind != TokenKind::Punct(",") && last_code.kind != TokenKind::Punct("|") || frame.member_line == Some(token.line)));
+                match frame.members.last_mut() {
+                    Some((_, doc)) if trailing && frame.member_line.is_some() => doc.extend(lines.iter().cloned()),
+                    _ => frame.pending.extend(lines.iter().cloned()),
+                }
+                continue;
+            },
+            TokenKind::Open(open) => {
+                // `(env:rust_type ? ...) <Name> < ... >` names the union that follows, so leave the docs for it
+                let is_name_metadata = *open == '<' && last_code.is_some_and(|last_code| last_code.kind == TokenKind::Close(')'));
+                let type_doc = if (*open == '{' || *open == '<') && !is_name_metadata { std::mem::take(&mut frame.pending) } else { Vec::new() };
+                stack.push(Frame::new(*open, type_doc));
+            },
+            TokenKind::Close(_) => {
+                if stack.len() > 1 {
+                    let frame = stack.pop().expect("Checked the stack is not empty");
+                    if (frame.open == '{' && !frame.is_value || frame.open == '<') && !frame.members.is_empty() {
+                        let member_names = frame.members.iter().map(|(name, _)| name.clone()).collect();
+                        let docs = TypeDocs {
+                            type_doc: frame.type_doc,
+                            member_docs: frame.members.into_iter().filter(|(_, doc)| !doc.is_empty()).collect(),
+                        };
+                        // Types without docs are kept too, so their docs aren't taken from another type with the same members
+                        types.push((member_names, docs));
+                    }
+                }
+            },
+            TokenKind::Word(word) if frame.expect_label => {
+                let is_label = match (frame.open, next_code) {
+                    ('{', Some(TokenKind::Punct(":"))) => true,
+                    ('{', Some(TokenKind::Punct("="))) => {
+                        frame.is_value = true;
+                        true
+                    },
+                    ('<', Some(TokenKind::Punct(":") | TokenKind::Punct("|") | TokenKind::Close('>'))) => true,
+                    _ => false,
+                };
+                if is_label {
+                    let doc = std::mem::take(&mut frame.pending);
+                    frame.members.push((word.to_string(), doc));
+                    frame.member_line = Some(token.line);
+                }
+                frame.expect_label = false;
+            },
+            TokenKind::Word(word) => {
+                match *word {
+                    // Only the comments directly above a `let` belong to it
+                    "let" => if last_code.is_some() && !matches!(tokens[index - 1].kind, TokenKind::Comment(_)) {
+                        frame.pending.clear();
+                    },
+                    "in" => {},
+                    // The name or type annotation of a `let`
+                    _ if last_code.is_some_and(|last_code| last_code.kind == TokenKind::Word("let") || last_code.kind == TokenKind::Punct(":")) => {},
+                    _ => frame.pending.clear(),
+                }
+            },
+            TokenKind::Punct(",") if frame.open == '{' => frame.expect_label = true,
+            TokenKind::Punct("|") if frame.open == '<' => frame.expect_label = true,
+            TokenKind::Punct(_) => {},
+            TokenKind::Import(_) | TokenKind::Other => frame.pending.clear(),
+        }
+        last_code = Some(token);
+    }
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn record_fields() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("person.dhall", "
+-- A person
+let Person = {
+    -- The full name
+    name : Text,
+    age : Natural, -- In years
+    {- Where they live,
+       if anywhere -}
+    address : Optional { street : Text }
+}
+in { Type = Person, default = { address = None { street : Text } } }
+");
+        let person = source_docs.get("person.dhall", ["name", "age", "address"]).unwrap();
+        assert_eq!(person.type_doc, docs(&["A person"]));
+        assert_eq!(person.member_doc("name"), docs(&["The full name"]));
+        assert_eq!(person.member_doc("age"), docs(&["In years"]));
+        assert_eq!(person.member_doc("address"), docs(&["Where they live,", "if anywhere"]));
+        assert!(source_docs.get("person.dhall", ["street"]).is_none());
+        assert!(source_docs.get("person.dhall", ["Type", "default"]).is_none());
+    }
+
+    #[test]
+    fn non_ascii_indent() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("point.dhall", "{ {- Across,\n\u{3000}\u{3000}  then\n  down -}\n x : Natural }");
+        let point = source_docs.get("point.dhall", ["x"]).unwrap();
+        assert_eq!(point.member_doc("x"), docs(&["Across,", "  then", "down"]));
+    }
+
+    #[test]
+    fn leading_commas() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("pair.dhall", "
+-- A pair of values
+\\(T : Type) ->
+  { -- The first value
+    first : T
+    -- The second value
+  , second : T
+  }
+");
+        let pair = source_docs.get("pair.dhall", ["second", "first"]).unwrap();
+        assert_eq!(pair.type_doc, docs(&["A pair of values"]));
+        assert_eq!(pair.member_doc("first"), docs(&["The first value"]));
+        assert_eq!(pair.member_doc("second"), docs(&["The second value"]));
+    }
+
+    #[test]
+    fn union_alternatives() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("shape.dhall", "
+-- Not a type
+let size = 4
+-- A shape
+let Shape = (env:rust_type ? (\\(T : Type) -> \\(T : Type) -> T)) <Shape> <
+    -- Round
+    Circle : { radius : Natural } |
+    Square -- Four sides
+>
+in Shape
+");
+        let shape = source_docs.get("shape.dhall", ["Circle", "Square"]).unwrap();
+        assert_eq!(shape.type_doc, docs(&["A shape"]));
+        assert_eq!(shape.member_doc("Circle"), docs(&["Round"]));
+        assert_eq!(shape.member_doc("Square"), docs(&["Four sides"]));
+    }
+
+    #[test]
+    fn prefers_same_file() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("a.dhall", "{ -- From a\n x : Text }");
+        source_docs.add_file("b.dhall", "{ -- From b\n x : Text }");
+        source_docs.add_file("c.dhall", "{ x : Text, y : ./a.dhall }");
+        assert_eq!(source_docs.get("b.dhall", ["x"]).unwrap().member_doc("x"), docs(&["From b"]));
+        assert_eq!(source_docs.get("c.dhall", ["x"]).unwrap().member_doc("x"), docs(&["From a"]));
+    }
+
+    #[test]
+    fn unrelated_files() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("person.dhall", "{ -- Their full name\n name : Text, age : Natural }");
+        source_docs.add_file("robot.dhall", "{ name : Text, age : Natural }");
+        assert!(source_docs.get("robot.dhall", ["name", "age"]).is_none());
+        assert_eq!(source_docs.get("person.dhall", ["name", "age"]).unwrap().member_doc("name"), docs(&["Their full name"]));
+    }
+
+    #[test]
+    fn same_members() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("line.dhall", "{ -- The start\n from : { x : Natural, y : Natural }, to : { -- The end\n x : Natural, y : Natural } }");
+        source_docs.add_file("size.dhall", "{ small : { -- Width\n x : Natural, y : Natural }, large : { -- Width\n x : Natural, y : Natural } }");
+        assert!(source_docs.get("line.dhall", ["x", "y"]).is_none());
+        assert_eq!(source_docs.get("size.dhall", ["x", "y"]).unwrap().member_doc("x"), docs(&["Width"]));
+    }
+
+    #[test]
+    fn nested_imports() {
+        let mut source_docs = SourceDocs::default();
+        source_docs.add_file("zoo/pet.dhall", "{ name : Text, owner : ../people/owner.dhall }");
+        source_docs.add_file("people/owner.dhall", "-- Owns a pet\n{ name : Text }");
+        source_docs.add_file("owner.dhall", "-- Not imported\n{ name : Text }");
+        assert_eq!(source_docs.get("zoo/pet.dhall", ["name"]).unwrap().type_doc, docs(&["Owns a pet"]));
+    }
+}
@@ -1,15 +1,24 @@
 use std::collections;
+use std::fmt::Write;
+use convert_case::{Case, Casing};
 use proc_macro2::Ident;
 use proc_macro2::Span;
-use proc_macro2::TokenStream;
 use serde_dhall::SimpleType;
 
-use crate::create_error;
+use crate::META_ENV_PHANTOM;
+use crate::META_ENV_RECURSIVE;
 use crate::NamedType;
 use crate::AggregateType;
 use crate::appendlist::AppendList;
 use crate::named_type::ClosedNamedType;
 use crate::named_type::IndexedIdent;
+use crate::named_type::TypeAlias;
+use crate::named_type::TypeName;
+use crate::options::MappedType;
+use crate::options::Pointer;
+use crate::aggregate_type::{alias_metadata, pointer_metadata, recursive_metadata, simple_type_is_form_of, simple_type_is_open_generic};
+use crate::type_origin::TypeOrigin;
+use crate::util::Error;
 use crate::util::IteratorSingle;
 
 #[derive(Debug)]
@@ -21,6 +30,12 @@ pub struct PrimitiveIdents {
     text: Ident,
     optional: Ident,
     list: Ident,
+    // The indirection for references to recursive types, and the pointers of `env:rust_pointer`
+    boxed: Ident,
+    rc: Ident,
+    arc: Ident,
+    // The type parameter of generic types
+    generic_parameter: Ident,
 }
 
 impl PrimitiveIdents {
@@ -33,21 +48,40 @@ impl PrimitiveIdents {
             text: Ident::new("String", span),
             optional: Ident::new("Option", span),
             list: Ident::new("Vec", span),
+            boxed: Ident::new("Box", span),
+            rc: Ident::new("Rc", span),
+            arc: Ident::new("Arc", span),
+            generic_parameter: Ident::new("T", span),
         }
     }
+
+    fn pointer_ident(&self, pointer: Pointer) -> &Ident {
+        match pointer {
+            Pointer::Box => &self.boxed,
+            Pointer::Rc => &self.rc,
+            Pointer::Arc => &self.arc,
+        }
+    }
+
+    /// The pointer `ident` stands for, if it is one of the pointer idents rather than a type of the same name
+    pub fn pointer(&self, ident: &Ident) -> Option<Pointer> {
+        [Pointer::Box, Pointer::Rc, Pointer::Arc].iter().copied().find(|pointer| std::ptr::eq(ident, self.pointer_ident(*pointer)))
+    }
 }
 
 // A named type that (optionally) has associated anonymous types
 #[derive(Debug)]
 struct RootType<'a> {
     ty: AggregateType<'a>,
-    anonymous_types: AppendList<AggregateType<'a>>
+    origin: TypeOrigin,
+    anonymous_types: AppendList<(AggregateType<'a>, TypeOrigin)>
 }
 
 impl<'a> RootType<'a> {
-    pub fn new(ty: AggregateType<'a>) -> Self {
+    pub fn new(ty: AggregateType<'a>, origin: TypeOrigin) -> Self {
         Self {
             ty,
+            origin,
             anonymous_types: AppendList::new()
         }
     }
@@ -68,20 +102,34 @@ where for<'a> &'a T: Into<&'a T> {
 pub struct TypeCollection<'a> {
     by_ident: collections::HashMap<Ident, RootType<'a>>,
     pending_by_ident: AppendList<(Ident, RootType<'a>)>,
+    aliases: AppendList<TypeAlias<'a>>,
     primitives: &'a PrimitiveIdents,
+    // The existing Rust types named types and aliases are mapped to by the `type_map` option
+    type_map: collections::HashMap<String, MappedType>,
 }
 
 impl<'a> TypeCollection<'a> {
-    pub fn new(primitives: &'a PrimitiveIdents) -> Self { 
+    pub fn new(primitives: &'a PrimitiveIdents, type_map: collections::HashMap<String, MappedType>) -> Self { 
         Self {
             by_ident: collections::HashMap::new(),
             pending_by_ident: AppendList::new(),
+            aliases: AppendList::new(),
             primitives,
+            type_map,
         }
     }
 
+    /// The existing Rust type the named type or alias `ident` is mapped to, if any
+    pub fn mapped_type(&self, ident: &Ident) -> Option<&MappedType> {
+        self.type_map.get(&ident.to_string())
+    }
+
+    pub fn primitives(&self) -> &'a PrimitiveIdents {
+        self.primitives
+    }
+
     pub fn get_by_ident(&self, ident: &Ident) -> Option<NamedType<'a, '_>> {
-        self.get_by_ident_internal(ident).map(|(ident, rt)| NamedType::new_named(ident, rt.ty))
+        self.get_by_ident_internal(ident).map(|(ident, rt)| NamedType::new_named(ident, rt.ty, &rt.origin))
     }
 
     fn get_by_ident_internal(&self, ident: &Ident) -> Option<(&Ident, &RootType<'a>)> {
@@ -94,18 +142,49 @@ impl<'a> TypeCollection<'a> {
         }
     }
 
-    pub fn get_by_structure<'p>(&self, context: Option<&Ident>, ty: AggregateType<'p>) -> Option<ClosedNamedType<'a, '_, 'p>> {
+    /// Find the type `ty` is a form of. It is an error for more than one type to match, as the Rust type to use would be ambiguous
+    pub fn get_by_structure<'p>(&self, context: Option<&Ident>, ty: AggregateType<'p>) -> Result<Option<ClosedNamedType<'a, '_, 'p>>, Error> {
         self.get_by_structure_internal(context, ty)
     }
 
-    fn get_by_structure_internal<'p>(&self, context: Option<&Ident>, ty: AggregateType<'p>) -> Option<ClosedNamedType<'a, '_, 'p>> {
-        self.iter_with_context_internal(context).filter_map(|nt| ty.is_form_of(nt.ty()).ok().map(|parameter| ClosedNamedType::new(nt, parameter))).single().ok()
+    fn get_by_structure_internal<'p>(&self, context: Option<&Ident>, ty: AggregateType<'p>) -> Result<Option<ClosedNamedType<'a, '_, 'p>>, Error> {
+        let mut matches: Vec<_> = self.iter_with_context_internal(context).filter_map(|nt| ty.is_form_of(nt.ty()).ok().map(|parameter| (nt, parameter))).collect();
+        if matches.len() > 1 {
+            let existing: Vec<_> = matches.into_iter().map(|(nt, _)| nt).collect();
+            Err(Self::structure_conflict_error(ty, &existing))
+        } else {
+            Ok(matches.pop().map(|(nt, parameter)| ClosedNamedType::new(nt, parameter)))
+        }
+    }
+
+    /// An error for a type that has the same structure as `existing` types, suggesting metadata to tell them apart
+    fn structure_conflict_error(ty: AggregateType, existing: &[NamedType]) -> Error {
+        fn suggested_name(nt: &NamedType) -> String {
+            match nt.origin().member_name() {
+                Some(member_name) if nt.ident().is_anonymous() => format!("{}{}", nt.ident().base_ident(), member_name.to_case(Case::Pascal)),
+                _ => nt.ident().to_string(),
+            }
+        }
+
+        let mut existing = existing.to_vec();
+        existing.sort_by_cached_key(|nt| nt.ident().to_string());
+
+        let mut msg = format!("The type `{}` has the same structure as {}:\n", ty, if existing.len() > 1 { "multiple types" } else { "an existing type" });
+        for nt in existing.iter() {
+            writeln!(msg, "    '{}' (defined at '{}')", nt.ident(), nt.origin()).expect("String::write_fmt should not fail");
+        }
+        msg.push_str("Dhall types are structural, so the Rust type to use is ambiguous. Give the types distinct names with Dhall metadata and import the intended type where it is used, for example:");
+        for nt in existing.iter() {
+            write!(msg, "\n    in '{}': {}", nt.origin(), nt.ty().with_name_metadata_example(&suggested_name(nt))).expect("String::write_fmt should not fail");
+        }
+
+        Error::new(msg)
     }
 
     pub fn iter(&self) -> impl Iterator<Item=NamedType<'a, '_>> {
         self.by_ident.iter()
         .chain(self.pending_by_ident.iter().map(|(ident, rt)| (ident, rt)))
-        .map(|(ident, rt)| NamedType::new_named(ident, rt.ty))
+        .map(|(ident, rt)| NamedType::new_named(ident, rt.ty, &rt.origin))
     }
 
     pub fn iter_with_context(&self, context: Option<&Ident>) -> impl Iterator<Item=NamedType<'a, '_>> {
@@ -117,50 +196,97 @@ impl<'a> TypeCollection<'a> {
             .chain(self.iter())
     }
 
+    /// Iterate the anonymous types belonging to the named type `context` (none if there is no such type)
     pub fn iter_anonymous_types(&self, context: &Ident) -> impl Iterator<Item=NamedType<'a, '_>> {
-        let (context, rt) = self.get_by_ident_internal(context).unwrap_or_else(|| panic!("Type with ident '{}' not found", context));
-        rt.anonymous_types.iter().enumerate().map(move |(index, ty)| NamedType::new_anonymous(context, index, *ty))
+        self.get_by_ident_internal(context).into_iter()
+            .flat_map(|(context, rt)| rt.anonymous_types.iter().enumerate().map(move |(index, (ty, origin))| NamedType::new_anonymous(context, index, *ty, origin)))
     }
 
-    pub fn get_or_create_by_ident(&self, ident: impl CanCow<Ident>, at: AggregateType<'a>) -> Result<NamedType<'a, '_>, TokenStream> {
-        if let Some(existing_at) = self.get_by_ident(ident.get_ref()) {
+    pub fn get_or_create_by_ident(&self, ident: impl CanCow<Ident>, at: AggregateType<'a>, origin: &TypeOrigin) -> Result<NamedType<'a, '_>, Error> {
+        if let Some(existing_alias) = self.get_alias(ident.get_ref()) {
+            Err(Error::new(format!("Named type '{}' has the same name as a type alias (defined at '{}')", ident.get_ref(), existing_alias.origin())))
+        } else if let Some(existing_at) = self.get_by_ident(ident.get_ref()) {
             if at.is_form_of(existing_at.ty()).is_ok() {
                 Ok(existing_at)
             } else {
-                Err(create_error(&format!("Named type '{}' does not match an existing type with the same name", ident.get_ref())))
+                Err(Error::new(format!("Named type '{}' does not match an existing type with the same name (defined at '{}')", ident.get_ref(), existing_at.origin())))
             }
         } else {
             let index = self.pending_by_ident.len();
-            self.pending_by_ident.push((ident.into(), RootType::new(at)));
+            self.pending_by_ident.push((ident.into(), RootType::new(at, origin.clone())));
             let (ident, rt) = &self.pending_by_ident[index];
-            Ok(NamedType::new_named(ident, rt.ty))
+            Ok(NamedType::new_named(ident, rt.ty, &rt.origin))
         }
     }
 
-    pub fn get_or_create_by_structure<'s>(&'s self, context: &'s Ident, at: AggregateType<'a>) -> Result<ClosedNamedType<'a, 's, 'a>, TokenStream> {
+    pub fn get_or_create_by_structure<'s>(&'s self, context: &'s Ident, at: AggregateType<'a>, origin: &TypeOrigin) -> Result<ClosedNamedType<'a, 's, 'a>, Error> {
         if let Some(name) = at.info()?.name_override() {
-            let nt = self.get_or_create_by_ident(Ident::new(name, Span::call_site()), at)?;
+            let nt = self.get_or_create_by_ident(Ident::new(name, Span::call_site()), at, origin)?;
             // PERF is_equivalent is run twice here, once inside `get_or_create_ident` discarding the type_parameter,
             // and once here, to get the type parameter
-            let type_parameter = at.is_form_of(nt.ty()).unwrap_or_else(|_| panic!("is_equivalent should not return inconsistent results ('{:?}' vs. '{:?}')", at, nt.ty()));
+            let type_parameter = at.is_form_of(nt.ty()).map_err(|_| Error::new(format!("Internal error: inconsistent structural match against named type '{}' (defined at '{}')", nt.ident(), nt.origin())))?;
             Ok(ClosedNamedType::new(nt, type_parameter))
-        } else if let Some(existing_nt) = self.get_by_structure(Some(context), at) {
+        } else if let Some(existing_nt) = self.get_by_structure(Some(context), at)? {
             Ok(existing_nt)
         } else {
-            Ok(ClosedNamedType::new(self.create_by_structure(context, at)?, None))
+            // An open generic anonymous type is referenced with the type parameter of the type it's nested in
+            let nt = self.create_by_structure(context, at, origin)?;
+            let type_parameter = at.is_form_of(nt.ty()).map_err(|_| Error::new(format!("Internal error: inconsistent structural match against anonymous type '{}' (defined at '{}')", nt.ident(), nt.origin())))?;
+            Ok(ClosedNamedType::new(nt, type_parameter))
         }
     }
 
-    fn create_by_structure<'s>(&'s self, context: &'s Ident, at: AggregateType<'a>) -> Result<NamedType<'a, 's>, TokenStream> {
-        if let Some(existing_nt) = self.get_by_structure(Some(context), at) {
-            Err(create_error(&format!("Anonymous type '{:?}' has the same structure as existing type '{}'", at, existing_nt.ident())))
+    fn create_by_structure<'s>(&'s self, context: &'s Ident, at: AggregateType<'a>, origin: &TypeOrigin) -> Result<NamedType<'a, 's>, Error> {
+        if let Some(existing_nt) = self.get_by_structure(Some(context), at)? {
+            Err(Self::structure_conflict_error(at, &[existing_nt.named_type()]))
+        } else if self.get_alias(context).is_some() {
+            Err(Error::new(format!("The records and unions in type alias '{}' must be named with Dhall metadata", context)))
         } else if let Some((_ident, rt)) = self.get_by_ident_internal(context) {
             let index = rt.anonymous_types.len();
-            rt.anonymous_types.push(at);
+            rt.anonymous_types.push((at, origin.clone()));
 
-            Ok(NamedType::new_anonymous(context, index, rt.anonymous_types[index]))
+            let (at, origin) = &rt.anonymous_types[index];
+            Ok(NamedType::new_anonymous(context, index, *at, origin))
         } else {
-            Err(create_error(&format!("Context type '{}' does not exist", context)))
+            Err(Error::new(format!("Context type '{}' does not exist", context)))
+        }
+    }
+
+    pub fn get_alias(&self, ident: &Ident) -> Option<&TypeAlias<'a>> {
+        self.aliases.iter().find(|alias| alias.ident() == ident)
+    }
+
+    pub fn iter_aliases(&self) -> impl Iterator<Item=&TypeAlias<'a>> {
+        self.aliases.iter()
+    }
+
+    /// The alias a file gives to the instantiation of a generic type `ty`, if any
+    pub fn get_instantiation_alias(&self, ty: AggregateType) -> Option<&TypeAlias<'a>> {
+        self.aliases.iter().find(|alias| alias.instantiation().is_some_and(|alias_ty| ty.is_form_of(alias_ty) == Ok(None)))
+    }
+
+    /// Get the type alias named `ident`, or create it for `ty`
+    pub fn get_or_create_alias(&self, ident: Ident, ty: &'a SimpleType, origin: &TypeOrigin) -> Result<&TypeAlias<'a>, Error> {
+        if let Some(existing_alias) = self.get_alias(&ident) {
+            if simple_type_is_form_of(ty, existing_alias.ty()).is_ok() {
+                Ok(existing_alias)
+            } else {
+                Err(Error::new(format!("Type alias '{}' does not match an existing type alias with the same name (defined at '{}')", ident, existing_alias.origin())))
+            }
+        } else if let Some(existing_nt) = self.get_by_ident(&ident) {
+            Err(Error::new(format!("Type alias '{}' has the same name as a named type (defined at '{}')", ident, existing_nt.origin())))
+        } else {
+            let index = self.aliases.len();
+            self.aliases.push(TypeAlias::new(ident, ty, origin.clone()));
+            Ok(&self.aliases[index])
+        }
+    }
+
+    /// The name references to the alias `ident` use, which is its Rust type if it is mapped by the `type_map` option
+    fn alias_name<'s>(&'s self, ident: &'s Ident) -> TypeName<'s> {
+        match self.mapped_type(ident) {
+            Some(mapped) => TypeName::Mapped(mapped),
+            None => IndexedIdent::new_named(ident).into(),
         }
     }
 
@@ -177,35 +303,88 @@ impl<'a> TypeCollection<'a> {
         }
     }
 
-    pub fn get_idents<'s>(&'s self, context_ident: &'s Ident, st: &'a SimpleType) -> Result<Vec<IndexedIdent<'s>>, TokenStream> {
-        fn aggregate_type<'a, 's>(tc: &'s TypeCollection<'a>, context_ident: &'s Ident, at: AggregateType<'a>, v: &mut Vec<IndexedIdent<'s>>) -> Result<(), TokenStream> {
-            let nt = tc.get_or_create_by_structure(context_ident, at)?;
-            v.push(nt.ident());
+    /// Get the chain of idents (outermost first) naming the Rust type for `st`, creating anonymous types as needed. 
+    /// `origin` is the location of the field or alternative being resolved.
+    /// References from a recursive type to itself are boxed, unless they are already behind a `Vec`.
+    /// Named types and aliases mapped by the `type_map` option are named by their Rust type instead.
+    pub fn get_idents<'s>(&'s self, context_ident: &'s Ident, st: &'a SimpleType, origin: &TypeOrigin) -> Result<Vec<TypeName<'s>>, Error> {
+        fn aggregate_type<'a, 's>(tc: &'s TypeCollection<'a>, context_ident: &'s Ident, at: AggregateType<'a>, origin: &TypeOrigin, is_indirect: bool, v: &mut Vec<TypeName<'s>>) -> Result<(), Error> {
+            let nt = tc.get_or_create_by_structure(context_ident, at, origin)?;
+            let mapped = if nt.ident().is_anonymous() { None } else { tc.mapped_type(nt.ident().base_ident()) };
+            if let Some(mapped) = mapped {
+                v.push(TypeName::Mapped(mapped));
+            } else if !is_indirect && !nt.ident().is_anonymous() && nt.ident().base_ident() == context_ident && nt.ty().info()?.is_recursive() {
+                v.push(IndexedIdent::new_named(&tc.primitives.boxed).into());
+            }
+            // Prefer the name a file gives to an instantiation of a generic type
+            if mapped.is_none() && nt.type_parameter().is_some() {
+                if let Some(alias) = tc.get_instantiation_alias(at) {
+                    v.push(tc.alias_name(alias.ident()));
+                    return Ok(());
+                }
+            }
+            if mapped.is_none() {
+                v.push(nt.ident().into());
+            }
             if let Some(parameter) = nt.type_parameter() {
-                simple_type(tc, context_ident, parameter, v)
+                simple_type(tc, context_ident, parameter, origin, false, v)
+            } else if nt.ty().info()?.phantom_parameter().is_some() {
+                Err(Error::new(format!("The type parameter of '{}' (defined at '{}') isn't used by its fields, so it can't be determined from the Dhall type. \
+                    Mark it in the definition with `//\\\\ (env:{} T ? {{}})`", nt.ident(), nt.named_type().origin(), META_ENV_PHANTOM)))
             } else {
                 Ok(())
             }
         }
 
-        fn simple_type<'s, 'a>(tc: &'s TypeCollection<'a>, context_ident: &'s Ident, st: &'a SimpleType, v: &mut Vec<IndexedIdent<'s>>) -> Result<(), TokenStream> {
+        fn simple_type<'s, 'a>(tc: &'s TypeCollection<'a>, context_ident: &'s Ident, st: &'a SimpleType, origin: &TypeOrigin, is_indirect: bool, v: &mut Vec<TypeName<'s>>) -> Result<(), Error> {
             match st {
-                SimpleType::Bool => v.push(IndexedIdent::new_named(&tc.primitives.bool)),
-                SimpleType::Natural => v.push(IndexedIdent::new_named(&tc.primitives.natural)),
-                SimpleType::Integer => v.push(IndexedIdent::new_named(&tc.primitives.integer)),
-                SimpleType::Double => v.push(IndexedIdent::new_named(&tc.primitives.double)),
-                SimpleType::Text => v.push(IndexedIdent::new_named(&tc.primitives.text)),
-                SimpleType::Optional(o) => { v.push(IndexedIdent::new_named(&tc.primitives.optional)); simple_type(tc, context_ident, &**o, v)? }
-                SimpleType::List(l) => { v.push(IndexedIdent::new_named(&tc.primitives.list)); simple_type(tc, context_ident, &**l, v)? },
-                SimpleType::Record(r) => aggregate_type(tc, context_ident, AggregateType::new_record(r), v)?,
-                SimpleType::Union(u) => aggregate_type(tc, context_ident, AggregateType::new_union(u), v)?,
+                SimpleType::Bool => v.push(IndexedIdent::new_named(&tc.primitives.bool).into()),
+                SimpleType::Natural => v.push(IndexedIdent::new_named(&tc.primitives.natural).into()),
+                SimpleType::Integer => v.push(IndexedIdent::new_named(&tc.primitives.integer).into()),
+                SimpleType::Double => v.push(IndexedIdent::new_named(&tc.primitives.double).into()),
+                SimpleType::Text => v.push(IndexedIdent::new_named(&tc.primitives.text).into()),
+                SimpleType::Optional(o) => { v.push(IndexedIdent::new_named(&tc.primitives.optional).into()); simple_type(tc, context_ident, o, origin, is_indirect, v)? }
+                SimpleType::List(l) => { v.push(IndexedIdent::new_named(&tc.primitives.list).into()); simple_type(tc, context_ident, l, origin, true, v)? },
+                SimpleType::Record(_) if recursive_metadata(st)?.is_some() => {
+                    let name = recursive_metadata(st)?.expect("Checked by the match guard");
+                    let nt = tc.get_by_ident(&Ident::new(name, Span::call_site()))
+                        .ok_or_else(|| Error::new(format!("`env:{} <{}>` doesn't refer to a named type, name the type it's used in `{}` with Dhall metadata", META_ENV_RECURSIVE, name, name)))?;
+                    match tc.mapped_type(nt.ident().base_ident()) {
+                        Some(mapped) => v.push(TypeName::Mapped(mapped)),
+                        None => {
+                            if !is_indirect {
+                                v.push(IndexedIdent::new_named(&tc.primitives.boxed).into());
+                            }
+                            v.push(nt.ident().into());
+                        },
+                    }
+                },
+                SimpleType::Record(_) if pointer_metadata(st)?.is_some() => {
+                    let (pointer, content) = pointer_metadata(st)?.expect("Checked by the match guard");
+                    v.push(IndexedIdent::new_named(tc.primitives.pointer_ident(pointer)).into());
+                    simple_type(tc, context_ident, content, origin, true, v)?;
+                },
+                SimpleType::Record(_) if alias_metadata(st)?.is_some() => {
+                    let (name, content) = alias_metadata(st)?.expect("Checked by the match guard");
+                    let alias = tc.get_or_create_alias(Ident::new(name, Span::call_site()), content, origin)?;
+                    v.push(tc.alias_name(alias.ident()));
+                    if simple_type_is_open_generic(alias.ty()) {
+                        let parameter = simple_type_is_form_of(content, alias.ty()).map_err(|_| Error::new(format!("Internal error: inconsistent structural match against type alias '{}' (defined at '{}')", alias.ident(), alias.origin())))?;
+                        if let Some(parameter) = parameter {
+                            simple_type(tc, context_ident, parameter, origin, false, v)?;
+                        }
+                    }
+                },
+                SimpleType::Record(r) => aggregate_type(tc, context_ident, AggregateType::new_record(r), origin, is_indirect, v)?,
+                SimpleType::Union(u) if AggregateType::new_union(u).is_generic_sentinel() => v.push(IndexedIdent::new_named(&tc.primitives.generic_parameter).into()),
+                SimpleType::Union(u) => aggregate_type(tc, context_ident, AggregateType::new_union(u), origin, is_indirect, v)?,
             }
 
             Ok(())
         }
 
         let mut v = Vec::new();
-        simple_type(self, context_ident, st, &mut v)?;
+        simple_type(self, context_ident, st, origin, false, &mut v)?;
 
         Ok(v)
     }
@@ -1,29 +1,96 @@
-use std::{borrow::Cow, fmt::Write};
+use std::{borrow::Cow, cell::{Cell, RefCell}, collections::{HashMap, HashSet}, fmt::Write};
 
 use convert_case::{Case, Casing};
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
-use serde_dhall::SimpleType;
+use quote::{format_ident, quote};
+use serde_dhall::{SimpleType, SimpleValue};
 
-use crate::{AggregateType, NamedType, TypeCollection, create_error, Options, named_type::IndexedIdent, type_collection::PrimitiveIdents, aggregate_type::{AggregateTypeRecordFieldsIter, AggregateTypeInfo, AggregateTypeUnionAlternativesIter}};
+use crate::{AggregateType, NamedType, TypeCollection, Options, options::Pointer, named_type::{IndexedIdent, TypeAlias, TypeName}, options::MappedType, source_docs::{SourceDocs, TypeDocs}, type_collection::PrimitiveIdents, hand_written::NamedTypes, type_origin::TypeOrigin, util::{Error, collect_results}, aggregate_type::{alias_metadata, metadata_content, pointer_metadata, recursive_metadata, simple_type_is_open_generic, simple_type_is_recursive, AggregateTypeMembers, AggregateTypeRecordFieldsIter, AggregateKind, AggregateTypeInfo, AggregateTypeUnionAlternativesIter, DhallTypeDisplay}};
 
 #[derive(Debug)]
 pub(crate) struct TypeGen<'a> {
     type_collection: TypeCollection<'a>,
+    // The `default` record of types defined by a schema
+    schema_defaults: HashMap<Ident, SimpleValue>,
+    // Comments written in the source of every .dhall file
+    source_docs: SourceDocs,
+    // Closed types defined by files, which may be instantiations of a generic type, so are only added once every file has been
+    closed_file_types: Vec<(&'a SimpleType, Ident, TypeOrigin)>,
+    // The type names and `Type.member` paths of the pointer options that matched something, so the rest can be reported
+    used_pointers: RefCell<HashSet<String>>,
+    // The type names and `Type.member` paths of the `type_map` option that matched something
+    used_type_map: RefCell<HashSet<String>>,
+    // Whether any enum has a catch-all variant, which needs the function ignoring unknown alternatives
+    has_catch_all_variants: Cell<bool>,
     options: Options,
 }
 
 impl<'a> TypeGen<'a> {
     pub fn new(primitives: &'a PrimitiveIdents, options: Options) -> Self {
         Self {
-            type_collection: TypeCollection::new(primitives),
+            type_collection: TypeCollection::new(primitives, Self::type_map(&options)),
+            schema_defaults: HashMap::new(),
+            source_docs: SourceDocs::default(),
+            closed_file_types: Vec::new(),
+            used_pointers: RefCell::new(HashSet::new()),
+            used_type_map: RefCell::new(HashSet::new()),
+            has_catch_all_variants: Cell::new(false),
             options,
         }
     }
 
-    pub fn add_type(&mut self, ty: AggregateType<'a>, file_ident: Ident) -> Result<NamedType, TokenStream> {
-        let ident = ty.info()?.name_override().map(|s| Ident::new(s, Span::call_site())).unwrap_or(file_ident);
-        self.type_collection.get_or_create_by_ident(ident, ty)
+    /// The existing Rust types used for named types and aliases: the hand-written types in the module, and those given with the `type_map` option
+    fn type_map(options: &Options) -> HashMap<String, MappedType> {
+        let hand_written_types = options.hand_written_types.iter().map(|hand_written| {
+            let ident = hand_written.ident();
+            (ident.to_string(), MappedType { ty: quote!(#ident), with: None })
+        });
+        let mapped_types = options.type_map.iter().map(|(path, mapped)| (path.clone(), mapped.value.clone()));
+        hand_written_types.chain(mapped_types).collect()
+    }
+
+    pub fn add_type(&mut self, st: &'a SimpleType, file_ident: Ident, origin: TypeOrigin, schema_default: Option<SimpleValue>) -> Result<(), Error> {
+        let ty = AggregateType::try_new(st).map_err(|e| e.in_origin(&origin))?;
+        let info = ty.info().map_err(|e| e.in_origin(&origin))?;
+        let ident = info.name_override().map(|s| Ident::new(s, Span::call_site())).unwrap_or(file_ident);
+        if let Some(schema_default) = schema_default {
+            self.schema_defaults.insert(ident.clone(), schema_default);
+        } else if info.name_override().is_none() && !info.is_open_generic() {
+            self.closed_file_types.push((st, ident, origin));
+            return Ok(());
+        }
+        self.type_collection.get_or_create_by_ident(ident, ty, &origin).map(|_| ()).map_err(|e| e.in_origin(&origin))
+    }
+
+    /// Add the closed types defined by files. A file applying a generic type (e.g. `Quantity Text`) becomes an alias of the instantiation, named after the file.
+    fn add_closed_file_types(&mut self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        for (st, ident, origin) in std::mem::take(&mut self.closed_file_types) {
+            let ty = AggregateType::try_new(st).expect("Only aggregate types are deferred");
+            let mut generic_types = self.type_collection.iter()
+                .filter(|nt| nt.ty().info().is_ok_and(|info| info.is_open_generic()))
+                .filter(|nt| matches!(ty.is_form_of(nt.ty()), Ok(Some(_))));
+            let result = match (generic_types.next(), generic_types.next()) {
+                (Some(generic_type), None) => match self.type_collection.get_instantiation_alias(ty) {
+                    Some(existing_alias) => Err(Error::new(format!("Type alias '{}' is the same instantiation of '{}' as type alias '{}' (defined at '{}')", ident, generic_type.ident(), existing_alias.ident(), existing_alias.origin()))),
+                    None => self.type_collection.get_or_create_alias(ident, st, &origin).map(|_| ()),
+                },
+                // Forms of more than one generic type are reported as ambiguous where they are used
+                _ => self.type_collection.get_or_create_by_ident(ident, ty, &origin).map(|_| ()),
+            };
+            if let Err(e) = result {
+                errors.push(e.in_origin(&origin));
+            }
+        }
+        errors
+    }
+
+    pub fn add_alias(&mut self, ident: Ident, ty: &'a SimpleType, origin: TypeOrigin) -> Result<(), Error> {
+        self.type_collection.get_or_create_alias(ident, ty, &origin).map(|_| ()).map_err(|e| e.in_origin(&origin))
+    }
+
+    pub fn add_source_docs(&mut self, origin: &TypeOrigin, source: &str) {
+        self.source_docs.add_file(origin.file(), source);
     }
 
     fn get_generic_parameter(index: u32) -> Ident {
@@ -36,33 +103,198 @@ impl<'a> TypeGen<'a> {
         Ident::new(&ident, Span::call_site())
     }
 
-    fn tokenize_field(&self, context_ident: &Ident, field_ident: Ident, field_type: &'a SimpleType) -> Result<TokenStream, TokenStream> {
-        let field_type_tokens = self.tokenize_type_ident(context_ident, field_type)?;
-        Ok(quote!(pub #field_ident: #field_type_tokens,))
+    fn tokenize_doc(lines: &[String]) -> TokenStream {
+        // `///` comments are sugar for `#[doc = "..."]`, including the leading space
+        let lines = lines.iter().map(|line| if line.is_empty() { String::new() } else { format!(" {}", line) });
+        quote!(#(#[doc = #lines])*)
+    }
+
+    /// Doc lines written in Dhall comments, followed by a blank line if there are any
+    fn source_doc_lines(doc: &[String]) -> Vec<String> {
+        let mut lines = doc.to_vec();
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines
+    }
+
+    /// The doc line naming the Dhall file and field path a type was generated from
+    fn source_line(origin: &TypeOrigin) -> String {
+        let field_path = origin.field_path();
+        if field_path.is_empty() {
+            format!("Generated from `{}`.", origin.file())
+        } else {
+            format!("Generated from `{}` in `{}`.", field_path, origin.file())
+        }
+    }
+
+    fn tokenize_type_doc(info: AggregateTypeInfo<'a>, ty: AggregateType<'a>, origin: &TypeOrigin, source_docs: Option<&TypeDocs>) -> TokenStream {
+        let source = Self::source_line(origin);
+        let generic = if info.is_open_generic() { "\\(T : Type) -> " } else { "" };
+
+        let mut doc_lines = Self::source_doc_lines(source_docs.map(|docs| docs.type_doc.as_slice()).unwrap_or_default());
+        doc_lines.extend([
+            source,
+            String::new(),
+            format!("Dhall type: `{}{}`", generic, ty),
+        ]);
+        Self::tokenize_doc(&doc_lines)
+    }
+
+    fn tokenize_field(&self, context_ident: &Ident, field_ident: Ident, field_type: &'a SimpleType, origin: &TypeOrigin, default: Option<&SimpleValue>, source_doc: &[String]) -> Result<TokenStream, Error> {
+        let field_type_tokens = self.tokenize_member_type(context_ident, field_type, origin, false)?;
+        let serde_with = self.tokenize_serde_with(context_ident, field_type, origin, false)?;
+        let field_visibility = self.options.field_visibility.value;
+
+        let mut doc_lines = Self::source_doc_lines(source_doc);
+        doc_lines.push(format!("Dhall type: `{}`", DhallTypeDisplay(field_type)));
+        // Skip defaults that can't be rendered (e.g. values of types with metadata)
+        if let Some(default) = default.and_then(|default| serde_dhall::serialize(default).type_annotation(field_type).to_string().ok()) {
+            doc_lines.push(String::new());
+            doc_lines.push(format!("Default: `{}`", default));
+        }
+        let doc = Self::tokenize_doc(&doc_lines);
+
+        Ok(quote!(
+            #doc
+            #serde_with
+            #field_visibility #field_ident: #field_type_tokens,
+        ))
     }
     
-    fn tokenize_variant(&self, context_ident: &Ident, variant_ident: Ident, variant_type: Option<&'a SimpleType>) -> Result<TokenStream, TokenStream> {
+    /// The fields of a union alternative's record payload, sorted by name, if the payload should be inlined as a struct variant.
+    /// Payloads that are named types (or forms of them) are never inlined, so only anonymous types are replaced.
+    fn inline_record_fields(&self, context_ident: &Ident, variant_type: Option<&'a SimpleType>) -> Result<Option<Vec<(&'a str, &'a SimpleType)>>, Error> {
+        if !self.options.inline_variant_records.value {
+            return Ok(None);
+        }
         if let Some(variant_type) = variant_type {
-            let variant_type_tokens = self.tokenize_type_ident(context_ident, variant_type)?;
-            Ok(quote!(#variant_ident(#variant_type_tokens),))
+            if metadata_content(variant_type).is_some() || simple_type_is_recursive(variant_type) {
+                return Ok(None);
+            }
+        }
+        let at = match variant_type.map(AggregateType::try_new) {
+            Some(Ok(at)) if !at.is_unit() && !at.is_generic_sentinel() => at,
+            _ => return Ok(None),
+        };
+        let info = at.info()?;
+        if info.name_override().is_some() {
+            return Ok(None);
+        }
+        if let Some(existing) = self.type_collection.get_by_structure(Some(context_ident), at)? {
+            if !existing.ident().is_anonymous() {
+                return Ok(None);
+            }
+        }
+
+        match info.iter_members()? {
+            AggregateTypeMembers::Record(r) => {
+                let mut fields: Vec<_> = r.collect();
+                fields.sort_by_key(|(field_name, _)| *field_name);
+                Ok(Some(fields))
+            },
+            AggregateTypeMembers::Union(_) => Ok(None),
+        }
+    }
+
+    /// serde_dhall can't serialize struct variants, but serde serializes variants with `serialize_with` as newtype variants.
+    /// Returns the attribute for an inlined variant, and a function serializing its fields as a record.
+    fn tokenize_variant_serializer(&self, context_ident: &Ident, enum_ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, variant_ident: &Ident, variant_type: Option<&'a SimpleType>, origin: &TypeOrigin) -> Result<(TokenStream, TokenStream), Error> {
+        let fields = match self.inline_record_fields(context_ident, variant_type)? {
+            Some(fields) => fields,
+            None => return Ok((TokenStream::new(), TokenStream::new())),
+        };
+        let field_idents: Vec<_> = fields.iter().map(|(field_name, _)| Ident::new(field_name, Span::call_site())).collect();
+        let field_type_tokens = fields.iter().map(|(field_name, field_type)| self.tokenize_member_type(context_ident, field_type, &origin.join(field_name), false)).collect::<Result<Vec<_>, Error>>()?;
+
+        let serializer_ident = Ident::new(&format!("__serialize_{}_{}", enum_ident, variant_ident), Span::call_site());
+        let generic_parameter = Self::get_generic_parameter(0);
+        // The type parameter is named explicitly, as it can't be inferred if no field uses it
+        let (serializer_path, generic, record_generic) = if info.is_open_generic() {
+            (format!("{}::<{}, _>", serializer_ident, generic_parameter), quote!(#generic_parameter: ::serde::Serialize,), quote!(, #generic_parameter))
         } else {
-            Ok(quote!(#variant_ident,))
+            (serializer_ident.to_string(), TokenStream::new(), TokenStream::new())
+        };
+        Ok((
+            quote!(#[serde(serialize_with = #serializer_path)]),
+            quote!(
+                #[allow(non_snake_case)]
+                fn #serializer_ident<#generic __S: ::serde::Serializer>(#(#field_idents: &#field_type_tokens,)* serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error> {
+                    #[derive(::serde::Serialize)]
+                    struct #variant_ident<'__a #record_generic> {
+                        #(#field_idents: &'__a #field_type_tokens,)*
+                    }
+                    ::serde::Serialize::serialize(&#variant_ident { #(#field_idents,)* }, serializer)
+                }
+            ),
+        ))
+    }
+
+    fn tokenize_variant(&self, context_ident: &Ident, variant_ident: Ident, variant_type: Option<&'a SimpleType>, variant_attribute: TokenStream, origin: &TypeOrigin, source_doc: &[String]) -> Result<TokenStream, Error> {
+        let mut doc_lines = Self::source_doc_lines(source_doc);
+        if let Some(fields) = self.inline_record_fields(context_ident, variant_type)? {
+            let source_docs = self.source_docs.get(origin.file(), fields.iter().map(|(field_name, _)| *field_name));
+            let mut field_tokens = Vec::new();
+            for (field_name, field_type) in fields {
+                let field_ident = Ident::new(field_name, Span::call_site());
+                let field_origin = origin.join(field_name);
+                let field_type_tokens = self.tokenize_member_type(context_ident, field_type, &field_origin, false)?;
+                // The fields are serialized by reference, which the adapter's `serialize` can't take
+                if !self.tokenize_serde_with(context_ident, field_type, &field_origin, false)?.is_empty() {
+                    return Err(Error::new("Rust types with a serde adapter can't be used in inlined union alternatives, disable `inline_variant_records` or map the alternative's type").in_origin(&field_origin));
+                }
+                let mut field_doc_lines = Self::source_doc_lines(source_docs.map(|docs| docs.member_doc(field_name)).unwrap_or_default());
+                field_doc_lines.push(format!("Dhall type: `{}`", DhallTypeDisplay(field_type)));
+                let field_doc = Self::tokenize_doc(&field_doc_lines);
+                field_tokens.push(quote!(
+                    #field_doc
+                    #field_ident: #field_type_tokens,
+                ));
+            }
+            doc_lines.push(format!("Dhall type: `{}`", DhallTypeDisplay(variant_type.expect("Inlined variants have a type"))));
+            let doc = Self::tokenize_doc(&doc_lines);
+            Ok(quote!(
+                #doc
+                #variant_attribute
+                #variant_ident { #(#field_tokens)* },
+            ))
+        } else if let Some(variant_type) = variant_type {
+            let variant_type_tokens = self.tokenize_member_type(context_ident, variant_type, origin, true)?;
+            let serde_with = self.tokenize_serde_with(context_ident, variant_type, origin, true)?;
+            doc_lines.push(format!("Dhall type: `{}`", DhallTypeDisplay(variant_type)));
+            let doc = Self::tokenize_doc(&doc_lines);
+            Ok(quote!(
+                #doc
+                #serde_with
+                #variant_ident(#variant_type_tokens),
+            ))
+        } else {
+            doc_lines.push("Dhall alternative without a value.".to_string());
+            let doc = Self::tokenize_doc(&doc_lines);
+            Ok(quote!(
+                #doc
+                #variant_ident,
+            ))
         }
     }
 
-    fn tokenize_field_impl(&self, context_ident: &Ident, field_ident: Ident, field_type: &'a SimpleType) -> Result<TokenStream, TokenStream> {
+    fn tokenize_field_impl(&self, context_ident: &Ident, field_ident: Ident, field_type: &'a SimpleType, origin: &TypeOrigin) -> Result<TokenStream, Error> {
         let get_func_str = field_ident.to_string().to_case(Case::Snake);
         let get_func_ident = Ident::new(&get_func_str, Span::call_site());
         let get_mut_func_ident = Ident::new(&format!("{}_mut", get_func_str), Span::call_site());
 
-        let variant_type_tokens = self.tokenize_type_ident(context_ident, field_type)?;
+        let variant_type_tokens = self.tokenize_member_type(context_ident, field_type, origin, false)?;
+        let get_doc = format!(" The `{}` field", field_ident);
+        let get_mut_doc = format!(" The `{}` field, mutably", field_ident);
 
         let get_func = quote!(
+            #[doc = #get_doc]
             pub fn #get_func_ident(&self) -> &#variant_type_tokens {
                 &self.#field_ident
             }
         );
         let get_mut_func = quote!(
+            #[doc = #get_mut_doc]
             pub fn #get_mut_func_ident(&mut self) -> &mut #variant_type_tokens {
                 &mut self.#field_ident
             }
@@ -74,22 +306,71 @@ impl<'a> TypeGen<'a> {
         ))
     }
 
-    fn tokenize_variant_impl(&self, context_ident: &Ident, variant_ident: Ident, variant_type: Option<&'a SimpleType>) -> Result<TokenStream, TokenStream> {
+    fn tokenize_variant_impl(&self, context_ident: &Ident, variant_ident: Ident, variant_type: Option<&'a SimpleType>, origin: &TypeOrigin) -> Result<TokenStream, Error> {
         let get_func_str = variant_ident.to_string().to_case(Case::Snake);
         let get_func_ident = Ident::new(&get_func_str, Span::call_site());
         let is_func_ident = Ident::new(&format!("is_{}", get_func_str), Span::call_site());
         let get_mut_func_ident = Ident::new(&format!("{}_mut", get_func_str), Span::call_site());
         let into_func_ident = Ident::new(&format!("into_{}", get_func_str), Span::call_site());
+        let is_doc = format!(" Whether the value is the `{}` alternative", variant_ident);
+        let get_doc = format!(" The value of the `{}` alternative, if it is that alternative", variant_ident);
+        let get_mut_doc = format!(" The value of the `{}` alternative mutably, if it is that alternative", variant_ident);
+        let into_doc = format!(" The value of the `{}` alternative, or `self` if it is another alternative", variant_ident);
 
         let mut is_func = TokenStream::new();
         let mut get_func = TokenStream::new();
         let mut get_mut_func = TokenStream::new();
         let mut into_func = TokenStream::new();
 
-        if let Some(variant_type) = variant_type {
-            let variant_type_tokens = self.tokenize_type_ident(context_ident, variant_type)?;
+        if let Some(fields) = self.inline_record_fields(context_ident, variant_type)? {
+            // Struct variants are accessed as tuples of their fields, in Dhall's (alphabetical) order
+            let field_idents: Vec<_> = fields.iter().map(|(field_name, _)| Ident::new(field_name, Span::call_site())).collect();
+            let field_type_tokens = fields.iter().map(|(field_name, field_type)| self.tokenize_member_type(context_ident, field_type, &origin.join(field_name), false)).collect::<Result<Vec<_>, Error>>()?;
 
+            is_func = quote!(
+                #[doc = #is_doc]
+                pub fn #is_func_ident(&self) -> bool {
+                    match self {
+                        Self::#variant_ident { .. } => true,
+                        _ => false,
+                    }
+                }
+            );
             get_func = quote!(
+                #[doc = #get_doc]
+                pub fn #get_func_ident(&self) -> ::core::option::Option<(#(&#field_type_tokens,)*)> {
+                    if let Self::#variant_ident { #(#field_idents),* } = self {
+                        Some((#(#field_idents,)*))
+                    } else {
+                        None
+                    }
+                }
+            );
+            get_mut_func = quote!(
+                #[doc = #get_mut_doc]
+                pub fn #get_mut_func_ident(&mut self) -> ::core::option::Option<(#(&mut #field_type_tokens,)*)> {
+                    if let Self::#variant_ident { #(#field_idents),* } = self {
+                        Some((#(#field_idents,)*))
+                    } else {
+                        None
+                    }
+                }
+            );
+            into_func = quote!(
+                #[doc = #into_doc]
+                pub fn #into_func_ident(self) -> ::core::result::Result<(#(#field_type_tokens,)*), Self> {
+                    if let Self::#variant_ident { #(#field_idents),* } = self {
+                        Ok((#(#field_idents,)*))
+                    } else {
+                        Err(self)
+                    }
+                }
+            );
+        } else if let Some(variant_type) = variant_type {
+            let variant_type_tokens = self.tokenize_member_type(context_ident, variant_type, origin, true)?;
+
+            get_func = quote!(
+                #[doc = #get_doc]
                 pub fn #get_func_ident(&self) -> ::core::option::Option<&#variant_type_tokens> {
                     if let Self::#variant_ident(value) = self {
                         Some(value)
@@ -99,6 +380,7 @@ impl<'a> TypeGen<'a> {
                 }
             );
             get_mut_func = quote!(
+                #[doc = #get_mut_doc]
                 pub fn #get_mut_func_ident(&mut self) -> ::core::option::Option<&mut #variant_type_tokens> {
                     if let Self::#variant_ident(value) = self {
                         Some(value)
@@ -108,6 +390,7 @@ impl<'a> TypeGen<'a> {
                 }
             );
             into_func = quote!(
+                #[doc = #into_doc]
                 pub fn #into_func_ident(self) -> ::core::result::Result<#variant_type_tokens, Self> {
                     if let Self::#variant_ident(value) = self {
                         Ok(value)
@@ -118,6 +401,7 @@ impl<'a> TypeGen<'a> {
             );
         } else {
             is_func = quote!(
+                #[doc = #is_doc]
                 pub fn #is_func_ident(&self) -> bool {
                     match self {
                         Self::#variant_ident => true,
@@ -135,7 +419,220 @@ impl<'a> TypeGen<'a> {
         ))
     }
 
-    fn tokenize_type_ident(&self, context_ident: &Ident, field_type: &'a SimpleType) -> Result<TokenStream, TokenStream> {
+    fn tokenize_type_ident(&self, context_ident: &Ident, field_type: &'a SimpleType, origin: &TypeOrigin) -> Result<TokenStream, Error> {
+        self.tokenize_type_ident_internal(context_ident, field_type, origin, true)
+    }
+
+    /// The Rust type for `field_type` without any pointers, which has the same Dhall type but, unlike the pointers, implements `StaticType`
+    fn tokenize_static_type_ident(&self, context_ident: &Ident, field_type: &'a SimpleType, origin: &TypeOrigin) -> Result<TokenStream, Error> {
+        self.tokenize_type_ident_internal(context_ident, field_type, origin, false)
+    }
+
+    /// The Rust type of a field or alternative payload, including the pointer the member itself is wrapped in
+    fn tokenize_member_type(&self, context_ident: &Ident, member_type: &'a SimpleType, origin: &TypeOrigin, is_variant: bool) -> Result<TokenStream, Error> {
+        let tokens = match self.member_mapped_type(context_ident, origin) {
+            Some(mapped) => mapped.ty.clone(),
+            None => self.tokenize_type_ident(context_ident, member_type, origin)?,
+        };
+        Ok(match self.member_pointer(context_ident, member_type, origin, is_variant) {
+            Some(pointer) => quote!(#pointer<#tokens>),
+            None => tokens,
+        })
+    }
+
+    /// The pointer a type name or `Type.member` path is wrapped in, recording that the option was used
+    fn pointer(&self, path: &str) -> Option<Pointer> {
+        let pointer = self.options.pointer(path);
+        if pointer.is_some() {
+            self.used_pointers.borrow_mut().insert(path.to_owned());
+        }
+        pointer
+    }
+
+    /// The `Type.member` path options use for a field or alternative payload
+    fn member_path(&self, context_ident: &Ident, origin: &TypeOrigin) -> Option<String> {
+        let context_origin = self.type_collection.get_by_ident(context_ident).map(|nt| nt.origin().clone())?;
+        origin.field_path_from(&context_origin).map(|field_path| format!("{}.{}", context_ident, field_path))
+    }
+
+    /// The pointer a field or alternative payload is wrapped in, from its `Type.member` path or `box_variants_over`
+    fn member_pointer(&self, context_ident: &Ident, member_type: &'a SimpleType, origin: &TypeOrigin, is_variant: bool) -> Option<Pointer> {
+        self.member_path(context_ident, origin)
+            .and_then(|path| self.pointer(&path))
+            .or_else(|| match self.options.box_variants_over.value {
+                Some(size) if is_variant && Self::estimate_size(member_type) > size => Some(Pointer::Box),
+                _ => None,
+            })
+    }
+
+    /// The existing Rust type a field or alternative payload is given by its `Type.member` path with the `type_map` option, recording that the option was used
+    fn member_mapped_type(&self, context_ident: &Ident, origin: &TypeOrigin) -> Option<&MappedType> {
+        let path = self.member_path(context_ident, origin)?;
+        let mapped = self.options.mapped_type(&path);
+        if mapped.is_some() {
+            self.used_type_map.borrow_mut().insert(path);
+        }
+        mapped
+    }
+
+    /// The chain of type names for `st` (see `TypeCollection::get_idents`), empty for the type parameter and `{}`, which aren't named types
+    fn get_type_names<'s>(&'s self, context_ident: &'s Ident, st: &'a SimpleType, origin: &TypeOrigin) -> Result<Vec<TypeName<'s>>, Error> {
+        if AggregateType::try_new(st).is_ok_and(|at| at.is_generic_sentinel() || at.is_unit()) {
+            return Ok(Vec::new());
+        }
+        self.type_collection.get_idents(context_ident, st, origin).map_err(|e| e.in_origin(origin))
+    }
+
+    /// The name of the Rust type used for the named type `ident`: its own, or the last segment of the existing Rust type it is mapped to
+    fn rust_type_name(&self, ident: &Ident) -> String {
+        let mapped_name = self.type_collection.mapped_type(ident).and_then(|mapped| match syn::parse2::<syn::Type>(mapped.ty.clone()) {
+            Ok(syn::Type::Path(path)) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+            _ => None,
+        });
+        mapped_name.unwrap_or_else(|| ident.to_string())
+    }
+
+    /// The name of the Rust type used for `st` if it is a named, non-generic record or union, to check hand-written types referring to it
+    fn named_type_rust_name(&self, st: &SimpleType) -> Option<String> {
+        let at = AggregateType::try_new(st).ok().filter(|at| !at.is_generic_sentinel() && !at.is_unit())?;
+        match self.type_collection.get_by_structure(None, at) {
+            Ok(Some(nt)) if !nt.ident().is_anonymous() && nt.type_parameter().is_none() => Some(self.rust_type_name(nt.ident().base_ident())),
+            _ => None,
+        }
+    }
+
+    /// The names of the Rust types used for every named record and union
+    fn named_type_rust_names(&self) -> HashSet<String> {
+        self.type_collection.iter_with_context(None)
+            .filter(|nt| !nt.ident().is_anonymous())
+            .map(|nt| self.rust_type_name(nt.ident().base_ident()))
+            .collect()
+    }
+
+    /// Whether the Rust type for `st` is or contains an existing Rust type given with the `type_map` option
+    fn refers_to_mapped_type(&self, context_ident: &Ident, st: &'a SimpleType, origin: &TypeOrigin) -> Result<bool, Error> {
+        Ok(self.get_type_names(context_ident, st, origin)?.iter().any(|name| matches!(name, TypeName::Mapped(_))))
+    }
+
+    /// The `#[serde(with = "...")]` attribute of a field or alternative payload whose Rust type was given an adapter with the `type_map` option
+    fn tokenize_serde_with(&self, context_ident: &Ident, member_type: &'a SimpleType, origin: &TypeOrigin, is_variant: bool) -> Result<TokenStream, Error> {
+        let mapped = match self.member_mapped_type(context_ident, origin) {
+            Some(mapped) => Some(mapped),
+            None => {
+                let names = self.get_type_names(context_ident, member_type, origin)?;
+                if names.iter().skip(1).any(|name| matches!(name, TypeName::Mapped(MappedType { with: Some(_), .. }))) {
+                    return Err(Error::new(format!("The serde adapter given with `type_map` only applies to fields and alternatives of the mapped type itself, not to `{}`", DhallTypeDisplay(member_type))).in_origin(origin));
+                }
+                match names.first() {
+                    Some(TypeName::Mapped(mapped)) => Some(*mapped),
+                    _ => None,
+                }
+            },
+        };
+        let with = match mapped.and_then(|mapped| mapped.with.as_ref()) {
+            Some(with) => with,
+            None => return Ok(TokenStream::new()),
+        };
+        if self.member_pointer(context_ident, member_type, origin, is_variant).is_some() {
+            return Err(Error::new("Rust types with a serde adapter can't be wrapped in a pointer").in_origin(origin));
+        }
+        Ok(quote!(#[serde(with = #with)]))
+    }
+
+    /// An expression for the Dhall type of a field or alternative payload, and whether its Rust type implements that `StaticType` itself, so the derive can be used.
+    /// Pointers are stripped, and types given with `type_map` needn't implement `StaticType`, so those are built from the Dhall type.
+    fn tokenize_member_static_type(&self, context_ident: &Ident, member_type: &'a SimpleType, origin: &TypeOrigin, is_variant: bool) -> Result<(TokenStream, bool), Error> {
+        if self.member_mapped_type(context_ident, origin).is_some() || self.refers_to_mapped_type(context_ident, member_type, origin)? {
+            return Ok((Self::tokenize_simple_type(member_type), false));
+        }
+        let tokens = self.tokenize_static_type_ident(context_ident, member_type, origin)?;
+        let is_derivable = self.tokenize_member_type(context_ident, member_type, origin, is_variant)?.to_string() == tokens.to_string();
+        Ok((quote!(<#tokens as ::serde_dhall::StaticType>::static_type()), is_derivable))
+    }
+
+    /// An expression building the Dhall type `st` without metadata, for Rust types that don't implement `StaticType`
+    fn tokenize_simple_type(st: &SimpleType) -> TokenStream {
+        if let Some(content) = metadata_content(st) {
+            return Self::tokenize_simple_type(content);
+        }
+        match st {
+            SimpleType::Bool => quote!(::serde_dhall::SimpleType::Bool),
+            SimpleType::Natural => quote!(::serde_dhall::SimpleType::Natural),
+            SimpleType::Integer => quote!(::serde_dhall::SimpleType::Integer),
+            SimpleType::Double => quote!(::serde_dhall::SimpleType::Double),
+            SimpleType::Text => quote!(::serde_dhall::SimpleType::Text),
+            SimpleType::Optional(st) => {
+                let tokens = Self::tokenize_simple_type(st);
+                quote!(::serde_dhall::SimpleType::Optional(::std::boxed::Box::new(#tokens)))
+            },
+            SimpleType::List(st) => {
+                let tokens = Self::tokenize_simple_type(st);
+                quote!(::serde_dhall::SimpleType::List(::std::boxed::Box::new(#tokens)))
+            },
+            SimpleType::Record(_) | SimpleType::Union(_) => {
+                let at = AggregateType::try_new(st);
+                if at.as_ref().is_ok_and(|at| at.is_generic_sentinel()) {
+                    let generic_parameter = Self::get_generic_parameter(0);
+                    return quote!(<#generic_parameter as ::serde_dhall::StaticType>::static_type());
+                }
+                let info = match at.and_then(AggregateType::info) {
+                    Ok(info) => info,
+                    Err(_) => return quote!(::serde_dhall::SimpleType::Union(::std::collections::HashMap::new())),
+                };
+                let is_record = matches!(info.kind(), AggregateKind::Record);
+                let member_tokens = info.iter_members().into_iter().flatten().map(|(member_name, member_type)| {
+                    let member_type = member_type.map(Self::tokenize_simple_type);
+                    match (is_record, member_type) {
+                        (true, member_type) => quote!((#member_name.to_owned(), #member_type)),
+                        (false, Some(member_type)) => quote!((#member_name.to_owned(), ::core::option::Option::Some(#member_type))),
+                        (false, None) => quote!((#member_name.to_owned(), ::core::option::Option::None)),
+                    }
+                });
+                let simple_type = if is_record { quote!(Record) } else { quote!(Union) };
+                quote!(::serde_dhall::SimpleType::#simple_type(::std::vec![#(#member_tokens),*].into_iter().collect()))
+            },
+        }
+    }
+
+    /// A rough estimate of the size in bytes of the Rust type for `st`, from its Dhall type alone
+    fn estimate_size(st: &SimpleType) -> u64 {
+        const POINTER_SIZE: u64 = 8;
+        const VEC_SIZE: u64 = 3 * POINTER_SIZE;
+
+        if recursive_metadata(st).ok().flatten().is_some() || pointer_metadata(st).ok().flatten().is_some() {
+            return POINTER_SIZE;
+        }
+        if let Some((_, content)) = alias_metadata(st).ok().flatten() {
+            return Self::estimate_size(content);
+        }
+        match st {
+            SimpleType::Bool => 1,
+            SimpleType::Natural | SimpleType::Double => 8,
+            SimpleType::Integer => 4,
+            SimpleType::Text | SimpleType::List(_) => VEC_SIZE,
+            SimpleType::Optional(st) => match st.as_ref() {
+                // `None` uses the niche of the non-null pointer
+                SimpleType::Text | SimpleType::List(_) => VEC_SIZE,
+                st => Self::estimate_size(st) + POINTER_SIZE,
+            },
+            SimpleType::Record(_) | SimpleType::Union(_) => {
+                let info = match AggregateType::try_new(st).and_then(AggregateType::info) {
+                    Ok(info) => info,
+                    Err(_) => return POINTER_SIZE,
+                };
+                if AggregateType::try_new(st).is_ok_and(AggregateType::is_generic_sentinel) {
+                    return POINTER_SIZE;
+                }
+                let member_sizes = info.iter_members().into_iter().flatten().map(|(_, st)| st.map_or(0, Self::estimate_size));
+                match info.kind() {
+                    AggregateKind::Record => member_sizes.sum(),
+                    AggregateKind::Union => member_sizes.max().unwrap_or(0) + POINTER_SIZE,
+                }
+            },
+        }
+    }
+
+    fn tokenize_type_ident_internal(&self, context_ident: &Ident, field_type: &'a SimpleType, origin: &TypeOrigin, with_pointers: bool) -> Result<TokenStream, Error> {
         if let Ok(at) = AggregateType::try_new(field_type) {
             if at.is_generic_sentinel() {
                 let ident = Self::get_generic_parameter(0);
@@ -146,94 +643,1143 @@ impl<'a> TypeGen<'a> {
             }
         }
         
-        let v = self.type_collection.get_idents(context_ident, field_type)?;
+        let v = self.type_collection.get_idents(context_ident, field_type, origin).map_err(|e| e.in_origin(origin))?;
         let mut iter = v.into_iter().rev();
 
-        let innermost = iter.next().ok_or_else(|| create_error("No Idents returned"))?;
-        let tokens = iter.fold(quote!(#innermost), |i, o| quote!(#o<#i>));
+        let innermost = iter.next().ok_or_else(|| Error::new("No Idents returned").in_origin(origin))?;
+        let mut tokens = match innermost {
+            TypeName::Ident(ident) => self.with_type_pointer(quote!(#ident), ident, with_pointers),
+            TypeName::Mapped(_) => quote!(#innermost),
+        };
+        for outer in iter {
+            tokens = match outer {
+                TypeName::Ident(ident) => match self.type_collection.primitives().pointer(ident.base_ident()) {
+                    Some(pointer) if with_pointers => quote!(#pointer<#tokens>),
+                    Some(_) => tokens,
+                    None => self.with_type_pointer(quote!(#ident<#tokens>), ident, with_pointers),
+                },
+                TypeName::Mapped(_) => quote!(#outer<#tokens>),
+            };
+        }
         Ok(tokens)
     }
 
-    fn tokenize_type(&self, context_ident: Option<&Ident>, rust_type: NamedType<'a, '_>) -> Result<TokenStream, TokenStream> {
-        let info = rust_type.ty().info()?;
-        //let ident = info.name_override().map(|s| Ident::new(s, Span::call_site())).unwrap_or(rust)
-        assert!(context_ident.is_some() || !rust_type.ident().is_anonymous());
+    /// Wrap `tokens`, a reference to the type `ident`, in the pointer every reference to the type is given by the `boxed`, `rc` or `arc` options
+    fn with_type_pointer(&self, tokens: TokenStream, ident: IndexedIdent<'_>, with_pointers: bool) -> TokenStream {
+        if !with_pointers || ident.is_anonymous() {
+            return tokens;
+        }
+        match self.pointer(&ident.to_string()) {
+            Some(pointer) => quote!(#pointer<#tokens>),
+            None => tokens,
+        }
+    }
+
+    fn tokenize_type(&self, context_ident: Option<&Ident>, rust_type: NamedType<'a, '_>) -> Result<TokenStream, Vec<Error>> {
+        let origin = rust_type.origin();
+        let info = rust_type.ty().info().map_err(|e| e.in_origin(origin))?;
+        if context_ident.is_none() && rust_type.ident().is_anonymous() {
+            return Err(Error::new(format!("Orphaned anonymous type '{}'", rust_type.ident())).in_origin(origin).into());
+        }
         let context_ident = context_ident.unwrap_or_else(|| rust_type.ident().base_ident());
         
-        match info.iter_members()? {
-            crate::aggregate_type::AggregateTypeMembers::Record(r) => self.tokenize_struct(context_ident, rust_type.ident(), info, r),
-            crate::aggregate_type::AggregateTypeMembers::Union(u) => self.tokenize_enum(context_ident, rust_type.ident(), info, u),
+        match info.iter_members().map_err(|e| e.in_origin(origin))? {
+            crate::aggregate_type::AggregateTypeMembers::Record(r) => self.tokenize_struct(context_ident, rust_type, info, r),
+            crate::aggregate_type::AggregateTypeMembers::Union(u) => self.tokenize_enum(context_ident, rust_type, info, u),
         }
     }
 
-    fn tokenize_struct(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, r: AggregateTypeRecordFieldsIter<'a>) -> Result<TokenStream, TokenStream> {
-        let mut tokens = TokenStream::new();
+    fn tokenize_struct(&self, context_ident: &Ident, rust_type: NamedType<'a, '_>, info: AggregateTypeInfo<'a>, r: AggregateTypeRecordFieldsIter<'a>) -> Result<TokenStream, Vec<Error>> {
+        let ident = rust_type.ident();
+        let origin = rust_type.origin();
+        let defaults = match self.schema_defaults.get(ident.ident().as_ref()) {
+            Some(SimpleValue::Record(defaults)) if !ident.is_anonymous() => Some(defaults),
+            _ => None,
+        };
+
+        let source_docs = self.source_docs.get(origin.file(), r.clone().map(|(field_name, _)| field_name));
+
+        // Resolve every field before giving up, so all bad fields are reported together
+        let tokens = collect_results(r.clone().map(|(field_name, field_type)| {
+            let default = defaults.and_then(|defaults| defaults.get(field_name));
+            let source_doc = source_docs.map(|docs| docs.member_doc(field_name)).unwrap_or_default();
+            self.tokenize_field(context_ident, Ident::new(field_name, Span::call_site()), field_type, &origin.join(field_name), default, source_doc)
+        }))?;
 
-        for (field_name, field_type) in r.clone() {
-            let field_tokens = self.tokenize_field(context_ident, Ident::new(field_name, Span::call_site()), field_type)?;
-            tokens.extend(field_tokens);
+        let generic_parameter = Self::get_generic_parameter(0);
+        let generic = if info.is_open_generic() { quote!(<#generic_parameter>) } else { TokenStream::new() };
+        // A type parameter no field uses still needs a field in Rust
+        let phantom_field = if info.phantom_parameter().is_some() {
+            let field_visibility = self.options.field_visibility.value;
+            quote!(
+                #[serde(skip)]
+                #field_visibility _marker: ::core::marker::PhantomData<#generic_parameter>,
+            )
+        } else {
+            TokenStream::new()
+        };
+        let impl_tokens = self.tokenize_struct_impl(context_ident, ident, info, r.clone(), origin)?;
+        let (static_type_derive, static_type_impl) = self.tokenize_static_type(context_ident, ident, info, r.clone().map(|(field_name, field_type)| (field_name, Some(field_type))), true, origin)?;
+        let loader_impl = Self::tokenize_loaders(ident, info.is_open_generic(), info.is_recursive() || self.has_catch_all_variant(rust_type.ty()));
+        let writer_impl = if info.is_recursive() {
+            let depth_exprs = r.map(|(field_name, field_type)| {
+                let field_ident = Ident::new(field_name, Span::call_site());
+                Self::tokenize_depth(field_type, quote!(self.#field_ident))
+            });
+            self.tokenize_recursive_writers(context_ident, ident, info, quote!(0usize #(.max(#depth_exprs))*), origin)?
+        } else {
+            Self::tokenize_writers(ident, info.is_open_generic())
+        };
+        let type_visibility = self.options.type_visibility.value;
+        let doc = Self::tokenize_type_doc(info, rust_type.ty(), origin, source_docs);
+        Ok(quote!(
+        #doc
+        #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize #static_type_derive)]
+        #type_visibility struct #ident #generic {
+            #(#tokens)*
+            #phantom_field
         }
+        #impl_tokens
+        #static_type_impl
+        #loader_impl
+        #writer_impl
+        ))
+    }
+
+    fn tokenize_enum(&self, context_ident: &Ident, rust_type: NamedType<'a, '_>, info: AggregateTypeInfo<'a>, u: AggregateTypeUnionAlternativesIter<'a>) -> Result<TokenStream, Vec<Error>> {
+        let ident = rust_type.ident();
+        let origin = rust_type.origin();
+        let source_docs = self.source_docs.get(origin.file(), u.clone().map(|(variant_name, _)| variant_name));
+        // Dhall unions are unordered, and `SimpleType` keeps their alternatives in a `HashMap`, so declare the variants by name.
+        // This is the documented order of `ALL` and of the derived `Ord` of nullary enums
+        let mut alternatives = u.clone().collect::<Vec<_>>();
+        alternatives.sort_by_key(|(variant_name, _)| *variant_name);
+        let mut serializers = Vec::new();
+        let tokens = collect_results(alternatives.iter().map(|&(variant_name, variant_type)| {
+            let variant_ident = Ident::new(variant_name, Span::call_site());
+            let variant_origin = origin.join(variant_name);
+            let (variant_attribute, serializer) = self.tokenize_variant_serializer(context_ident, ident, info, &variant_ident, variant_type, &variant_origin)?;
+            serializers.push(serializer);
+            let source_doc = source_docs.map(|docs| docs.member_doc(variant_name)).unwrap_or_default();
+            self.tokenize_variant(context_ident, variant_ident, variant_type, variant_attribute, &variant_origin, source_doc)
+        }))?;
+
         let generic_parameter = Self::get_generic_parameter(0);
         let generic = if info.is_open_generic() { quote!(<#generic_parameter>) } else { TokenStream::new() };
-        let impl_tokens = self.tokenize_struct_impl(context_ident, ident, info, r)?;
+        // serde doesn't infer bounds for fields serialized with `serialize_with`
+        let serde_bound = if info.is_open_generic() && serializers.iter().any(|tokens| !tokens.is_empty()) {
+            let bound = format!("{}: ::serde::Serialize", generic_parameter);
+            quote!(#[serde(bound(serialize = #bound))])
+        } else {
+            TokenStream::new()
+        };
+        let catch_all = self.tokenize_catch_all_variant(ident, info).map_err(|e| e.in_origin(origin))?;
+        let (deserialize_derive, deserialize_impl) = if catch_all.is_empty() {
+            (quote!(, ::serde::Deserialize), TokenStream::new())
+        } else {
+            (TokenStream::new(), Self::tokenize_catch_all_deserialize(ident, info, &tokens))
+        };
+        let impl_tokens = self.tokenize_enum_impl(context_ident, ident, info, &alternatives, origin)?;
+        let conversion_impl = self.tokenize_payload_conversions(context_ident, ident, info, &alternatives, origin)?;
+        let variant_names = alternatives.iter().map(|(variant_name, _)| *variant_name).collect::<Vec<_>>();
+        let is_nullary = !info.is_open_generic() && alternatives.iter().all(|(_, variant_type)| variant_type.is_none());
+        let (nullary_derive, nullary_impl) = if is_nullary {
+            // The catch-all variant holds the unknown alternative, which can't be copied or ordered
+            let nullary_derive = if catch_all.is_empty() { quote!(Copy, PartialOrd, Ord,) } else { TokenStream::new() };
+            let mut nullary_impl = Self::tokenize_nullary_enum_impl(ident, &variant_names, !catch_all.is_empty());
+            if self.options.kind_enums.value {
+                nullary_impl.extend(Self::tokenize_nullary_kind(ident, !catch_all.is_empty()));
+            }
+            (nullary_derive, nullary_impl)
+        } else if self.options.kind_enums.value {
+            (TokenStream::new(), self.tokenize_kind_enum(ident, info, &variant_names, !catch_all.is_empty()).map_err(|e| e.in_origin(origin))?)
+        } else {
+            (TokenStream::new(), TokenStream::new())
+        };
+        let (static_type_derive, static_type_impl) = self.tokenize_static_type(context_ident, ident, info, u.clone(), false, origin)?;
+        let loader_impl = Self::tokenize_loaders(ident, info.is_open_generic(), info.is_recursive() || self.has_catch_all_variant(rust_type.ty()));
+        let writer_impl = if info.is_recursive() {
+            let catch_all_arm = if catch_all.is_empty() { TokenStream::new() } else { quote!(_ => 0,) };
+            let depth_arms = u.map(|(variant_name, variant_type)| {
+                let variant_ident = Ident::new(variant_name, Span::call_site());
+                match variant_type {
+                    Some(variant_type) => {
+                        let depth = Self::tokenize_depth(variant_type, quote!(value));
+                        quote!(Self::#variant_ident(value) => #depth,)
+                    },
+                    None => quote!(Self::#variant_ident => 0,),
+                }
+            });
+            self.tokenize_recursive_writers(context_ident, ident, info, quote!(match self { #(#depth_arms)* #catch_all_arm }), origin)?
+        } else {
+            Self::tokenize_writers(ident, info.is_open_generic())
+        };
+        let type_visibility = self.options.type_visibility.value;
+        let doc = Self::tokenize_type_doc(info, rust_type.ty(), origin, source_docs);
+        let non_exhaustive = if catch_all.is_empty() { TokenStream::new() } else { quote!(#[non_exhaustive]) };
+
         Ok(quote!(
-        #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize)]
-        pub struct #ident #generic {
-            #tokens
+        #doc
+        #[derive(Debug, Clone, #nullary_derive Eq, PartialEq, Hash, ::serde::Serialize #deserialize_derive #static_type_derive)]
+        #serde_bound
+        #non_exhaustive
+        #type_visibility enum #ident #generic {
+            #(#tokens)*
+            #catch_all
         }
+        #deserialize_impl
+        #(#serializers)*
         #impl_tokens
+        #nullary_impl
+        #conversion_impl
+        #static_type_impl
+        #loader_impl
+        #writer_impl
         ))
     }
 
-    fn tokenize_enum(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, u: AggregateTypeUnionAlternativesIter<'a>) -> Result<TokenStream, TokenStream> {
+    /// `From` each payload type into the enum `ident` and `TryFrom` the enum back into it, for payloads whose type no other alternative has.
+    /// Generic enums only get them for a single payload that isn't just the type parameter, as an instantiation could make two payload types the same.
+    /// Pointers to the enum itself already have `TryFrom` through their `From<T>`, so can only be converted into the enum.
+    fn tokenize_payload_conversions(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, alternatives: &[(&'a str, Option<&'a SimpleType>)], origin: &TypeOrigin) -> Result<TokenStream, Error> {
+        let generic_parameter = Self::get_generic_parameter(0);
+        let generic = if info.is_open_generic() { quote!(<#generic_parameter>) } else { TokenStream::new() };
+
+        // Payloads are told apart by their Dhall type as well as their Rust type, as different Rust types can name the same type (e.g. type aliases)
+        let mut payloads = Vec::new();
+        for &(variant_name, variant_type) in alternatives {
+            let variant_type = match variant_type {
+                Some(variant_type) => variant_type,
+                None => continue,
+            };
+            let variant_origin = origin.join(variant_name);
+            let type_tokens = if self.inline_record_fields(context_ident, Some(variant_type))?.is_some() {
+                None
+            } else {
+                Some(self.tokenize_member_type(context_ident, variant_type, &variant_origin, true)?)
+            };
+            let target = self.tokenize_static_type_ident(context_ident, variant_type, &variant_origin)?.to_string();
+            if info.is_open_generic() && generic_parameter == target {
+                return Ok(TokenStream::new());
+            }
+            let has_try_from = target != quote!(#ident #generic).to_string();
+            payloads.push((variant_name, DhallTypeDisplay(variant_type).to_string(), type_tokens, has_try_from));
+        }
+        if info.is_open_generic() && payloads.len() > 1 {
+            return Ok(TokenStream::new());
+        }
+
         let mut tokens = TokenStream::new();
+        for (variant_name, dhall_type, type_tokens, has_try_from) in payloads.iter() {
+            let type_tokens = match type_tokens {
+                Some(type_tokens) => type_tokens,
+                None => continue,
+            };
+            let is_unique = payloads.iter()
+                .filter(|(_, other_dhall_type, other_type_tokens, _)| other_dhall_type == dhall_type || other_type_tokens.as_ref().is_some_and(|other| other.to_string() == type_tokens.to_string()))
+                .count() == 1;
+            if !is_unique {
+                continue;
+            }
+            let variant_ident = Ident::new(variant_name, Span::call_site());
+            tokens.extend(quote!(
+            impl #generic ::core::convert::From<#type_tokens> for #ident #generic {
+                fn from(value: #type_tokens) -> Self {
+                    Self::#variant_ident(value)
+                }
+            }
+            ));
+            if *has_try_from {
+                tokens.extend(quote!(
+                impl #generic ::core::convert::TryFrom<#ident #generic> for #type_tokens {
+                    type Error = #ident #generic;
+
+                    fn try_from(value: #ident #generic) -> ::core::result::Result<Self, Self::Error> {
+                        match value {
+                            #ident::#variant_ident(value) => ::core::result::Result::Ok(value),
+                            value => ::core::result::Result::Err(value),
+                        }
+                    }
+                }
+                ));
+            }
+        }
+        Ok(tokens)
+    }
 
-        for (variant_name, variant_type) in u.clone() {
-            let variant_tokens = self.tokenize_variant(context_ident, Ident::new(variant_name, Span::call_site()), variant_type)?;
-            tokens.extend(variant_tokens);
+    /// `From` conversions between records with the same Dhall type that were given different names (e.g. with `rust_struct`), if their fields have the same Rust types
+    fn tokenize_record_conversions(&self) -> TokenStream {
+        let mut records = Vec::new();
+        for nt in self.type_collection.iter_with_context(None) {
+            let ident = nt.ident();
+            if ident.is_anonymous() || self.type_collection.mapped_type(ident.base_ident()).is_some() {
+                continue;
+            }
+            let r = match nt.ty().info() {
+                Ok(info) if !info.is_open_generic() => match info.iter_members() {
+                    Ok(AggregateTypeMembers::Record(r)) => r,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            // Types with invalid fields are reported where they are generated
+            let fields = r.map(|(field_name, field_type)| self.tokenize_member_type(ident.base_ident(), field_type, &nt.origin().join(field_name), false)
+                    .map(|type_tokens| (field_name, type_tokens.to_string())))
+                .collect::<Result<Vec<_>, Error>>();
+            if let Ok(mut fields) = fields {
+                fields.sort();
+                records.push((nt.ty().to_string(), ident, fields));
+            }
         }
 
+        let mut tokens = TokenStream::new();
+        for (from_type, from_ident, from_fields) in records.iter() {
+            for (to_type, to_ident, to_fields) in records.iter() {
+                if from_ident == to_ident || from_type != to_type || from_fields != to_fields {
+                    continue;
+                }
+                let field_idents = from_fields.iter().map(|(field_name, _)| Ident::new(field_name, Span::call_site())).collect::<Vec<_>>();
+                tokens.extend(quote!(
+                impl ::core::convert::From<#from_ident> for #to_ident {
+                    fn from(value: #from_ident) -> Self {
+                        Self {
+                            #(#field_idents: value.#field_idents,)*
+                        }
+                    }
+                }
+                ));
+            }
+        }
+        tokens
+    }
+
+    /// A nullary `<Enum>Kind` enum with the alternatives of the enum `ident`, and functions telling which alternative a value is
+    fn tokenize_kind_enum(&self, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, variant_names: &[&str], has_catch_all: bool) -> Result<TokenStream, Error> {
+        let kind_ident = format_ident!("{}Kind", ident.ident().as_ref());
+        if self.type_collection.get_by_ident(&kind_ident).is_some() || self.type_collection.get_alias(&kind_ident).is_some() {
+            return Err(Error::new(format!("Enum '{}' can't have a kind enum, as the type '{}' already exists", ident, kind_ident)));
+        }
+        let variant_idents = variant_names.iter().map(|variant_name| Ident::new(variant_name, Span::call_site())).collect::<Vec<_>>();
+        let (catch_all_variant, catch_all_arm, non_exhaustive) = if has_catch_all {
+            let catch_all_ident = Self::catch_all_variant_ident();
+            (quote!(#catch_all_ident,), quote!(Self::#catch_all_ident(_) => #kind_ident::#catch_all_ident,), quote!(#[non_exhaustive]))
+        } else {
+            (TokenStream::new(), TokenStream::new(), TokenStream::new())
+        };
+        let nullary_impl = Self::tokenize_nullary_enum_impl(IndexedIdent::new_named(&kind_ident), variant_names, has_catch_all);
         let generic_parameter = Self::get_generic_parameter(0);
         let generic = if info.is_open_generic() { quote!(<#generic_parameter>) } else { TokenStream::new() };
-        let impl_tokens = self.tokenize_enum_impl(context_ident, ident, info, u)?;
+        let type_visibility = self.options.type_visibility.value;
+        let doc = Self::tokenize_doc(&[format!("The alternatives of [`{}`], without their values", ident)]);
 
         Ok(quote!(
-        #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize, ::serde_dhall::StaticType)]
-        pub enum #ident #generic {
-            #tokens
+        #doc
+        #[derive(Debug, Clone, Copy, PartialOrd, Ord, Eq, PartialEq, Hash)]
+        #non_exhaustive
+        #type_visibility enum #kind_ident {
+            #(#variant_idents,)*
+            #catch_all_variant
+        }
+        #nullary_impl
+
+        impl #generic #ident #generic {
+            /// Which alternative the value is
+            pub fn kind(&self) -> #kind_ident {
+                match self {
+                    #(Self::#variant_idents { .. } => #kind_ident::#variant_idents,)*
+                    #catch_all_arm
+                }
+            }
+
+            /// The Dhall name of the alternative
+            pub fn name(&self) -> &'static str {
+                self.kind().as_str()
+            }
         }
-        #impl_tokens
         ))
     }
+
+    /// `kind()` and `name()` of an enum whose alternatives have no values, which is its own kind enum
+    fn tokenize_nullary_kind(ident: IndexedIdent<'_>, has_catch_all: bool) -> TokenStream {
+        // The catch-all variant keeps the unknown alternative, so the enum isn't `Copy`
+        let kind = if has_catch_all { quote!(self.clone()) } else { quote!(*self) };
+
+        quote!(
+        impl #ident {
+            /// Which alternative the value is, the value itself as the alternatives have no values
+            pub fn kind(&self) -> Self {
+                #kind
+            }
+
+            /// The Dhall name of the alternative
+            pub fn name(&self) -> &'static str {
+                self.as_str()
+            }
+        }
+        )
+    }
+
+    /// The alternative names, `Display` and `FromStr` of enums whose alternatives have no values, which are used like C-like enums.
+    /// The catch-all variant isn't one of the Dhall alternatives, so it's left out of `ALL` and `COUNT` and can't be parsed.
+    fn tokenize_nullary_enum_impl(ident: IndexedIdent<'_>, variant_names: &[&str], has_catch_all: bool) -> TokenStream {
+        let variant_idents = variant_names.iter().map(|variant_name| Ident::new(variant_name, Span::call_site())).collect::<Vec<_>>();
+        let count = variant_names.len();
+        let catch_all_arm = if has_catch_all {
+            let catch_all_ident = Self::catch_all_variant_ident();
+            let catch_all_name = catch_all_ident.to_string();
+            quote!(Self::#catch_all_ident { .. } => #catch_all_name,)
+        } else {
+            TokenStream::new()
+        };
+        let parse_error = format!("'{{}}' is not an alternative of {}, expected one of {}", ident, variant_names.join(", "));
+
+        quote!(
+        impl #ident {
+            /// Every alternative, ordered by name
+            pub const ALL: [Self; #count] = [#(Self::#variant_idents),*];
+            /// The number of alternatives
+            pub const COUNT: usize = #count;
+
+            /// The Dhall name of the alternative
+            pub fn as_str(&self) -> &'static str {
+                match *self {
+                    #(Self::#variant_idents => #variant_names,)*
+                    #catch_all_arm
+                }
+            }
+        }
+
+        impl ::core::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ::core::str::FromStr for #ident {
+            type Err = ::std::string::String;
+
+            /// Parse the Dhall name of an alternative
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                match s {
+                    #(#variant_names => ::core::result::Result::Ok(Self::#variant_idents),)*
+                    _ => ::core::result::Result::Err(::std::format!(#parse_error, s)),
+                }
+            }
+        }
+        )
+    }
     
-    fn tokenize_struct_impl(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, r: AggregateTypeRecordFieldsIter<'a>) -> Result<TokenStream, TokenStream> {
+    /// Implement `StaticType` with the Dhall type the type was generated from, as `(derive, impl)` tokens.
+    /// Types without pointers, types given with `type_map` or a type parameter use the derive. It can't require `T: StaticType` for generic types, 
+    /// and pointers don't implement `StaticType`, so those get a hand-written impl using the types the pointers point to.
+    /// Recursive types have no single Dhall type, so don't implement it at all.
+    fn tokenize_static_type(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, members: impl Iterator<Item=(&'a str, Option<&'a SimpleType>)>, is_record: bool, origin: &TypeOrigin) -> Result<(TokenStream, TokenStream), Error> {
+        if info.is_recursive() {
+            return Ok((TokenStream::new(), TokenStream::new()));
+        }
+
+        // The catch-all variant has no Dhall type, so mustn't be derived as an alternative
+        let mut is_derivable = is_record || !self.has_catch_all(ident, info);
+        let mut member_tokens = Vec::new();
+        for (member_name, member_type) in members {
+            let member_origin = origin.join(member_name);
+            let inline_fields = if is_record { None } else { self.inline_record_fields(context_ident, member_type).map_err(|e| e.in_origin(origin))? };
+            if let Some(fields) = inline_fields {
+                let field_names = fields.iter().map(|(field_name, _)| *field_name);
+                let mut field_static_types = Vec::new();
+                for (field_name, field_type) in fields.iter() {
+                    let (tokens, is_field_derivable) = self.tokenize_member_static_type(context_ident, field_type, &member_origin.join(field_name), false)?;
+                    is_derivable &= is_field_derivable;
+                    field_static_types.push(tokens);
+                }
+                member_tokens.push(quote!((#member_name.to_owned(), ::core::option::Option::Some(::serde_dhall::SimpleType::Record(::std::vec![#((#field_names.to_owned(), #field_static_types)),*].into_iter().collect())))));
+                continue;
+            }
+            let member_static_type = match member_type {
+                Some(member_type) => {
+                    let (tokens, is_member_derivable) = self.tokenize_member_static_type(context_ident, member_type, &member_origin, !is_record)?;
+                    is_derivable &= is_member_derivable;
+                    Some(tokens)
+                },
+                None => None,
+            };
+            member_tokens.push(match (is_record, member_static_type) {
+                (true, Some(member_static_type)) => quote!((#member_name.to_owned(), #member_static_type)),
+                (true, None) => return Err(Error::new(format!("Record field '{}' has no type", member_name)).in_origin(origin)),
+                (false, Some(member_static_type)) => quote!((#member_name.to_owned(), ::core::option::Option::Some(#member_static_type))),
+                (false, None) => quote!((#member_name.to_owned(), ::core::option::Option::None)),
+            });
+        }
+
+        if !info.is_open_generic() && is_derivable {
+            return Ok((quote!(, ::serde_dhall::StaticType), TokenStream::new()));
+        }
+
+        let simple_type = if is_record { quote!(Record) } else { quote!(Union) };
+        let generic_parameter = Self::get_generic_parameter(0);
+        let (impl_generic, generic) = if info.is_open_generic() {
+            (quote!(<#generic_parameter: ::serde_dhall::StaticType>), quote!(<#generic_parameter>))
+        } else {
+            (TokenStream::new(), TokenStream::new())
+        };
+        Ok((TokenStream::new(), quote!(
+        impl #impl_generic ::serde_dhall::StaticType for #ident #generic {
+            fn static_type() -> ::serde_dhall::SimpleType {
+                ::serde_dhall::SimpleType::#simple_type(::std::vec![#(#member_tokens),*].into_iter().collect())
+            }
+        }
+        )))
+    }
+
+    /// Whether the enum `ident` gets a catch-all variant, from `catch_all_variants` or `env:rust_catch_all`
+    fn has_catch_all(&self, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>) -> bool {
+        info.has_catch_all() || self.options.catch_all(Self::type_name(ident).as_deref())
+    }
+
+    /// The variant deserialized from alternatives the Dhall type of the enum `ident` doesn't have, if it has one
+    fn tokenize_catch_all_variant(&self, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>) -> Result<TokenStream, Error> {
+        if !self.has_catch_all(ident, info) {
+            return Ok(TokenStream::new());
+        }
+        let variant_ident = Self::catch_all_variant_ident();
+        if info.get_member(&variant_ident.to_string()).is_ok() {
+            return Err(Error::new(format!("Union '{}' already has an alternative named '{}', so it can't have a catch-all variant", ident, variant_ident)));
+        }
+        let unknown_alternative = Self::unknown_alternative_ident();
+        if self.type_collection.get_by_ident(&unknown_alternative).is_some() || self.type_collection.get_alias(&unknown_alternative).is_some() {
+            return Err(Error::new(format!("Enum '{}' can't have a catch-all variant, as the type '{}' already exists", ident, unknown_alternative)));
+        }
+        self.has_catch_all_variants.set(true);
+        Ok(quote!(
+            /// Any alternative the Dhall type this enum was generated from doesn't have, such as one added by a newer version of the type,
+            /// with its name and value. It isn't part of the Dhall type, so serializing it is an error.
+            #[serde(skip_serializing)]
+            #variant_ident(#unknown_alternative),
+        ))
+    }
+
+    fn catch_all_variant_ident() -> Ident {
+        Ident::new("Unknown", Span::call_site())
+    }
+
+    fn unknown_alternative_ident() -> Ident {
+        Ident::new("UnknownAlternative", Span::call_site())
+    }
+
+    fn replay_alternative_ident() -> Ident {
+        Ident::new("__DhallAlternative", Span::call_site())
+    }
+
+    /// `Deserialize` for the enum `ident` with a catch-all variant, which serde's `#[serde(other)]` can only give a unit variant.
+    /// The name of the alternative is read first. Known alternatives are then read by the derive of a copy of the enum without the catch-all variant,
+    /// using serde's `remote`, and any other alternative becomes the catch-all variant along with its value.
+    fn tokenize_catch_all_deserialize(ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, variant_tokens: &[TokenStream]) -> TokenStream {
+        let generic_parameter = Self::get_generic_parameter(0);
+        let (impl_generic, generic) = if info.is_open_generic() {
+            (quote!(, #generic_parameter: ::serde::Deserialize<'de>), quote!(<#generic_parameter>))
+        } else {
+            (TokenStream::new(), TokenStream::new())
+        };
+        let remote = quote!(#ident).to_string();
+        let variant_names = info.iter_members().into_iter().flatten().map(|(variant_name, _)| variant_name).collect::<Vec<_>>();
+        let catch_all_ident = Self::catch_all_variant_ident();
+        let unknown_alternative = Self::unknown_alternative_ident();
+        let replay_alternative = Self::replay_alternative_ident();
+
+        quote!(
+        impl<'de #impl_generic> ::serde::Deserialize<'de> for #ident #generic {
+            fn deserialize<__D: ::serde::Deserializer<'de>>(deserializer: __D) -> ::core::result::Result<Self, __D::Error> {
+                const VARIANTS: &[&str] = &[#(#variant_names),*];
+
+                #[derive(::serde::Deserialize)]
+                #[serde(remote = #remote)]
+                #[allow(dead_code)]
+                enum __Known #generic {
+                    #(#variant_tokens)*
+                }
+
+                struct __Visitor #generic (::core::marker::PhantomData<#ident #generic>);
+
+                impl<'de #impl_generic> ::serde::de::Visitor<'de> for __Visitor #generic {
+                    type Value = #ident #generic;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                        f.write_str("a union alternative")
+                    }
+
+                    fn visit_enum<__A: ::serde::de::EnumAccess<'de>>(self, data: __A) -> ::core::result::Result<Self::Value, __A::Error> {
+                        let (name, variant) = data.variant::<::std::string::String>()?;
+                        if VARIANTS.contains(&name.as_str()) {
+                            __Known::deserialize(#replay_alternative { name, variant })
+                        } else {
+                            // Alternatives without a value can't be read as one
+                            let value = ::serde::de::VariantAccess::newtype_variant::<::serde_dhall::SimpleValue>(variant).ok();
+                            ::core::result::Result::Ok(#ident::#catch_all_ident(#unknown_alternative { name, value }))
+                        }
+                    }
+                }
+
+                deserializer.deserialize_enum(#remote, VARIANTS, __Visitor(::core::marker::PhantomData))
+            }
+        }
+        )
+    }
+
+    /// The type of the catch-all variants, and the deserializer giving the derive of the known alternatives an alternative whose name was already read
+    fn tokenize_unknown_alternative(&self) -> TokenStream {
+        let unknown_alternative = Self::unknown_alternative_ident();
+        let replay_alternative = Self::replay_alternative_ident();
+        let type_visibility = self.options.type_visibility.value;
+        quote!(
+        /// An alternative the Dhall type of an enum doesn't have, kept by the enum's catch-all variant
+        #[derive(Debug, Clone, Eq, PartialEq)]
+        #type_visibility struct #unknown_alternative {
+            /// The name of the alternative
+            pub name: ::std::string::String,
+            /// The value of the alternative, `None` if it has none
+            pub value: ::core::option::Option<::serde_dhall::SimpleValue>,
+        }
+
+        // `SimpleValue` isn't `Hash`, so only the name is hashed, which equal alternatives share
+        impl ::core::hash::Hash for #unknown_alternative {
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                self.name.hash(state)
+            }
+        }
+
+        struct #replay_alternative<__V> {
+            name: ::std::string::String,
+            variant: __V,
+        }
+
+        impl<'de, __V: ::serde::de::VariantAccess<'de>> ::serde::Deserializer<'de> for #replay_alternative<__V> {
+            type Error = __V::Error;
+
+            fn deserialize_any<__W: ::serde::de::Visitor<'de>>(self, visitor: __W) -> ::core::result::Result<__W::Value, Self::Error> {
+                visitor.visit_enum(self)
+            }
+
+            ::serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+                bytes byte_buf option unit unit_struct newtype_struct seq tuple
+                tuple_struct map struct enum identifier ignored_any
+            }
+        }
+
+        impl<'de, __V: ::serde::de::VariantAccess<'de>> ::serde::de::EnumAccess<'de> for #replay_alternative<__V> {
+            type Error = __V::Error;
+            type Variant = __V;
+
+            fn variant_seed<__S: ::serde::de::DeserializeSeed<'de>>(self, seed: __S) -> ::core::result::Result<(__S::Value, __V), __V::Error> {
+                let Self { name, variant } = self;
+                seed.deserialize(::serde::de::IntoDeserializer::<'de, __V::Error>::into_deserializer(name)).map(|value| (value, variant))
+            }
+        }
+        )
+    }
+
+    /// The name options use for the type `ident`, `None` for anonymous types
+    fn type_name(ident: IndexedIdent<'_>) -> Option<String> {
+        (!ident.is_anonymous()).then(|| ident.to_string())
+    }
+
+    /// Whether values of the type `at` may contain alternatives of generated enums with a catch-all variant
+    fn has_catch_all_variant(&self, at: AggregateType<'a>) -> bool {
+        let info = match at.info() {
+            Ok(info) => info,
+            Err(_) => return false,
+        };
+        if matches!(info.kind(), AggregateKind::Union) {
+            let nt = self.type_collection.get_by_structure(None, at).ok().flatten();
+            // Types given with `type_map` or written by hand aren't generated
+            if nt.as_ref().is_some_and(|nt| self.type_collection.mapped_type(nt.ident().base_ident()).is_some()) {
+                return false;
+            }
+            if info.has_catch_all() || self.options.catch_all(nt.map(|nt| nt.ident().to_string()).as_deref()) {
+                return true;
+            }
+        }
+        info.iter_members().into_iter().flatten()
+            .filter_map(|(_, member_type)| member_type)
+            .any(|member_type| self.simple_type_has_catch_all_variant(member_type))
+    }
+
+    fn simple_type_has_catch_all_variant(&self, st: &'a SimpleType) -> bool {
+        // References to recursive types are checked where the type is defined
+        if recursive_metadata(st).ok().flatten().is_some() {
+            return false;
+        }
+        if let Some(content) = metadata_content(st) {
+            return self.simple_type_has_catch_all_variant(content);
+        }
+        match st {
+            SimpleType::Optional(st) | SimpleType::List(st) => self.simple_type_has_catch_all_variant(st),
+            SimpleType::Record(_) | SimpleType::Union(_) => match AggregateType::try_new(st) {
+                Ok(at) if !at.is_generic_sentinel() => self.has_catch_all_variant(at),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Functions loading the type from Dhall with its static type annotation, for every non-anonymous type.
+    /// Values of recursive types are loaded without an annotation, as the depth of their Dhall type isn't known,
+    /// as are values of types with catch-all variants, which accept alternatives their Dhall type doesn't have.
+    fn tokenize_loaders(ident: IndexedIdent<'_>, is_open_generic: bool, without_annotation: bool) -> TokenStream {
+        if ident.is_anonymous() {
+            return TokenStream::new();
+        }
+
+        let generic_parameter = Self::get_generic_parameter(0);
+        let (impl_generic, generic) = match (is_open_generic, without_annotation) {
+            (true, false) => (quote!(<#generic_parameter: ::serde_dhall::StaticType + ::serde::de::DeserializeOwned>), quote!(<#generic_parameter>)),
+            (true, true) => (quote!(<#generic_parameter: ::serde::de::DeserializeOwned>), quote!(<#generic_parameter>)),
+            (false, _) => (TokenStream::new(), TokenStream::new()),
+        };
+
+        if without_annotation {
+            return quote!(
+            impl #impl_generic #ident #generic {
+                /// Load a value from a .dhall file
+                pub fn from_dhall_file(path: impl ::core::convert::AsRef<::std::path::Path>) -> ::serde_dhall::Result<Self> {
+                    Self::from_dhall_file_with_imports(path, true)
+                }
+
+                /// Load a value from a .dhall file.
+                /// Imports within the file are only resolved if `imports` is `true`.
+                pub fn from_dhall_file_with_imports(path: impl ::core::convert::AsRef<::std::path::Path>, imports: bool) -> ::serde_dhall::Result<Self> {
+                    ::serde_dhall::from_file(path).imports(imports).parse::<Self>()
+                }
+
+                /// Load a value from Dhall source.
+                /// Imports are resolved relative to the current directory.
+                pub fn from_dhall_str(source: &str) -> ::serde_dhall::Result<Self> {
+                    ::serde_dhall::from_str(source).parse::<Self>()
+                }
+            }
+            );
+        }
+        let load_error = Self::load_error_ident();
+
+        quote!(
+        impl #impl_generic #ident #generic {
+            /// Load a value from a .dhall file, checking it against the Dhall type this type was generated from
+            pub fn from_dhall_file(path: impl ::core::convert::AsRef<::std::path::Path>) -> ::serde_dhall::Result<Self> {
+                Self::from_dhall_file_with_imports(path, true)
+            }
+
+            /// Load a value from a .dhall file, checking it against the Dhall type this type was generated from.
+            /// Imports within the file are only resolved if `imports` is `true`.
+            pub fn from_dhall_file_with_imports(path: impl ::core::convert::AsRef<::std::path::Path>, imports: bool) -> ::serde_dhall::Result<Self> {
+                let path = path.as_ref();
+                ::serde_dhall::from_file(path).imports(imports).static_type_annotation().parse::<Self>()
+                    .map_err(|error| #load_error(error, ::serde_dhall::from_file(path).imports(imports).parse(), &<Self as ::serde_dhall::StaticType>::static_type()))
+            }
+
+            /// Load a value from Dhall source, checking it against the Dhall type this type was generated from.
+            /// Imports are resolved relative to the current directory.
+            pub fn from_dhall_str(source: &str) -> ::serde_dhall::Result<Self> {
+                ::serde_dhall::from_str(source).static_type_annotation().parse::<Self>()
+                    .map_err(|error| #load_error(error, ::serde_dhall::from_str(source).parse(), &<Self as ::serde_dhall::StaticType>::static_type()))
+            }
+        }
+        )
+    }
+
+    /// Functions writing the type as Dhall annotated with its static type, which the loaders can read back
+    fn tokenize_writers(ident: IndexedIdent<'_>, is_open_generic: bool) -> TokenStream {
+        let generic_parameter = Self::get_generic_parameter(0);
+        let (impl_generic, generic) = if is_open_generic {
+            (quote!(<#generic_parameter: ::serde_dhall::StaticType + ::serde::Serialize>), quote!(<#generic_parameter>))
+        } else {
+            (TokenStream::new(), TokenStream::new())
+        };
+
+        quote!(
+        impl #impl_generic #ident #generic {
+            /// Render the value as Dhall text, annotated with the Dhall type this type was generated from
+            pub fn to_dhall_string(&self) -> ::serde_dhall::Result<::std::string::String> {
+                let value = ::serde_dhall::serialize(self).static_type_annotation().to_string()?;
+                ::core::result::Result::Ok(::std::format!("{} : {}", value, <Self as ::serde_dhall::StaticType>::static_type()))
+            }
+
+            /// Write the value to a .dhall file, annotated with the Dhall type this type was generated from
+            pub fn write_dhall_file(&self, path: impl ::core::convert::AsRef<::std::path::Path>) -> ::std::io::Result<()> {
+                let dhall = self.to_dhall_string().map_err(|error| ::std::io::Error::new(::std::io::ErrorKind::InvalidData, error.to_string()))?;
+                ::std::fs::write(path, dhall + "\n")
+            }
+        }
+        )
+    }
+
+    /// Functions writing a type that refers to recursive types. The Dhall type a value is written with is unrolled as deep as the value is, 
+    /// which is only possible for types that refer to nothing but themselves, and a `__dhall_depth` for finding that depth.
+    /// `depth` is an expression for the greatest depth of the members of `self`.
+    fn tokenize_recursive_writers(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, depth: TokenStream, origin: &TypeOrigin) -> Result<TokenStream, Error> {
+        let generic_parameter = Self::get_generic_parameter(0);
+        let (depth_generic, writer_generic, generic) = if info.is_open_generic() {
+            (quote!(<#generic_parameter>), quote!(<#generic_parameter: ::serde_dhall::StaticType + ::serde::Serialize>), quote!(<#generic_parameter>))
+        } else {
+            (TokenStream::new(), TokenStream::new(), TokenStream::new())
+        };
+        let depth = if ident.is_anonymous() { depth } else { quote!(1 + #depth) };
+        let depth_impl = quote!(
+        impl #depth_generic #ident #generic {
+            #[doc(hidden)]
+            pub fn __dhall_depth(&self) -> usize {
+                #depth
+            }
+        }
+        );
+
+        let dhall_type = match ident.is_anonymous() {
+            false => self.tokenize_recursive_dhall_type_members(context_ident, &ident.ident(), info, origin)?,
+            true => None,
+        };
+        let dhall_type = match dhall_type {
+            Some(dhall_type) => dhall_type,
+            None => return Ok(depth_impl),
+        };
+
+        Ok(quote!(
+        #depth_impl
+        impl #writer_generic #ident #generic {
+            /// The Dhall type this type was generated from, with its references to itself unrolled `depth` times.
+            /// The innermost references are the empty union `<>`.
+            pub fn dhall_type_with_depth(depth: usize) -> ::serde_dhall::SimpleType {
+                if depth == 0 {
+                    return ::serde_dhall::SimpleType::Union(::std::collections::HashMap::new());
+                }
+                #dhall_type
+            }
+
+            /// Render the value as Dhall text, annotated with the Dhall type this type was generated from, unrolled as deep as the value
+            pub fn to_dhall_string(&self) -> ::serde_dhall::Result<::std::string::String> {
+                let ty = Self::dhall_type_with_depth(self.__dhall_depth());
+                let value = ::serde_dhall::serialize(self).type_annotation(&ty).to_string()?;
+                ::core::result::Result::Ok(::std::format!("{} : {}", value, ty))
+            }
+
+            /// Write the value to a .dhall file, annotated with the Dhall type this type was generated from, unrolled as deep as the value
+            pub fn write_dhall_file(&self, path: impl ::core::convert::AsRef<::std::path::Path>) -> ::std::io::Result<()> {
+                let dhall = self.to_dhall_string().map_err(|error| ::std::io::Error::new(::std::io::ErrorKind::InvalidData, error.to_string()))?;
+                ::std::fs::write(path, dhall + "\n")
+            }
+        }
+        ))
+    }
+
+    /// An expression for how deeply values of recursive types are nested in `value`, a value of the Dhall type `st`
+    fn tokenize_depth(st: &'a SimpleType, value: TokenStream) -> TokenStream {
+        if !simple_type_is_recursive(st) {
+            return quote!(0);
+        }
+        match st {
+            SimpleType::Optional(st) => {
+                let depth = Self::tokenize_depth(st, quote!(value));
+                quote!(#value.as_ref().map_or(0, |value| #depth))
+            },
+            SimpleType::List(st) => {
+                let depth = Self::tokenize_depth(st, quote!(value));
+                quote!(#value.iter().map(|value| #depth).max().unwrap_or(0))
+            },
+            _ => match metadata_content(st) {
+                Some(content) => Self::tokenize_depth(content, value),
+                _ => quote!(#value.__dhall_depth()),
+            },
+        }
+    }
+
+    /// An expression building the Dhall type `st`, a member of the recursive type `ident`, where references to `ident` are unrolled `depth - 1` times.
+    /// `None` if `st` refers to other recursive types, as their depth isn't known.
+    fn tokenize_recursive_dhall_type(&self, context_ident: &Ident, ident: &Ident, st: &'a SimpleType, origin: &TypeOrigin) -> Result<Option<TokenStream>, Error> {
+        if !simple_type_is_recursive(st) {
+            let (tokens, _) = self.tokenize_member_static_type(context_ident, st, origin, false)?;
+            return Ok(Some(tokens));
+        }
+
+        let self_type = quote!(Self::dhall_type_with_depth(depth - 1));
+        match st {
+            SimpleType::Optional(st) => Ok(self.tokenize_recursive_dhall_type(context_ident, ident, st, origin)?
+                .map(|tokens| quote!(::serde_dhall::SimpleType::Optional(::std::boxed::Box::new(#tokens))))),
+            SimpleType::List(st) => Ok(self.tokenize_recursive_dhall_type(context_ident, ident, st, origin)?
+                .map(|tokens| quote!(::serde_dhall::SimpleType::List(::std::boxed::Box::new(#tokens))))),
+            _ => {
+                if let Some(name) = recursive_metadata(st).map_err(|e| e.in_origin(origin))? {
+                    return Ok((ident == name).then_some(self_type));
+                }
+                if let Some(content) = metadata_content(st) {
+                    return self.tokenize_recursive_dhall_type(context_ident, ident, content, origin);
+                }
+                let at = AggregateType::try_new(st).map_err(|e| e.in_origin(origin))?;
+                match self.type_collection.get_by_structure(Some(context_ident), at).map_err(|e| e.in_origin(origin))? {
+                    Some(nt) if !nt.ident().is_anonymous() => Ok((nt.ident().ident().as_ref() == ident).then_some(self_type)),
+                    _ => self.tokenize_recursive_dhall_type_members(context_ident, ident, at.info().map_err(|e| e.in_origin(origin))?, origin),
+                }
+            },
+        }
+    }
+
+    /// The members of a record or union for `tokenize_recursive_dhall_type`
+    fn tokenize_recursive_dhall_type_members(&self, context_ident: &Ident, ident: &Ident, info: AggregateTypeInfo<'a>, origin: &TypeOrigin) -> Result<Option<TokenStream>, Error> {
+        let mut member_tokens = Vec::new();
+        let is_record = matches!(info.kind(), AggregateKind::Record);
+        for (member_name, member_type) in info.iter_members().map_err(|e| e.in_origin(origin))? {
+            let member_type = match member_type {
+                Some(member_type) => match self.tokenize_recursive_dhall_type(context_ident, ident, member_type, &origin.join(member_name))? {
+                    Some(tokens) if is_record => tokens,
+                    Some(tokens) => quote!(::core::option::Option::Some(#tokens)),
+                    None => return Ok(None),
+                },
+                None => quote!(::core::option::Option::None),
+            };
+            member_tokens.push(quote!((#member_name.to_owned(), #member_type)));
+        }
+
+        let simple_type = if is_record { quote!(Record) } else { quote!(Union) };
+        Ok(Some(quote!(::serde_dhall::SimpleType::#simple_type(::std::vec![#(#member_tokens),*].into_iter().collect()))))
+    }
+
+    fn load_error_ident() -> Ident {
+        Ident::new("__dhall_load_error", Span::call_site())
+    }
+
+    /// A function shared by every loader, which starts the message of a type error with the path of the first value not matching the expected type.
+    /// serde_dhall's type errors only point at the annotation, so the value is loaded again without it to find the mismatch, which only happens when loading failed.
+    fn tokenize_load_error() -> TokenStream {
+        let load_error = Self::load_error_ident();
+        quote!(
+        #[allow(dead_code)]
+        fn #load_error(error: ::serde_dhall::Error, value: ::serde_dhall::Result<::serde_dhall::SimpleValue>, ty: &::serde_dhall::SimpleType) -> ::serde_dhall::Error {
+            use ::serde_dhall::{NumKind, SimpleType, SimpleValue};
+
+            fn describe(value: &SimpleValue) -> ::std::string::String {
+                match value {
+                    SimpleValue::Num(NumKind::Bool(_)) => "Bool".into(),
+                    SimpleValue::Num(NumKind::Natural(_)) => "Natural".into(),
+                    SimpleValue::Num(NumKind::Integer(_)) => "Integer".into(),
+                    SimpleValue::Num(NumKind::Double(_)) => "Double".into(),
+                    SimpleValue::Text(_) => "Text".into(),
+                    SimpleValue::Optional(_) => "an Optional".into(),
+                    SimpleValue::List(_) => "a List".into(),
+                    SimpleValue::Record(_) => "a record".into(),
+                    SimpleValue::Union(alternative, _) => ::std::format!("the alternative `{}`", alternative),
+                }
+            }
+
+            fn join(member: &str, path: ::std::string::String) -> ::std::string::String {
+                if path.is_empty() || path.starts_with('[') {
+                    ::std::format!("{}{}", member, path)
+                } else {
+                    ::std::format!("{}.{}", member, path)
+                }
+            }
+
+            // serde_dhall reads `toMap` style lists of `{ mapKey : Text, mapValue : T }` as records
+            fn map_value_type(entry: &SimpleType) -> ::core::option::Option<&SimpleType> {
+                match entry {
+                    SimpleType::Record(fields) if fields.len() == 2 && fields.get("mapKey") == Some(&SimpleType::Text) => fields.get("mapValue"),
+                    _ => None,
+                }
+            }
+
+            // The path and description of the first mismatch between `ty` and `value`
+            fn mismatch(ty: &SimpleType, value: &SimpleValue) -> ::core::option::Option<(::std::string::String, ::std::string::String)> {
+                match (ty, value) {
+                    (SimpleType::Bool, SimpleValue::Num(NumKind::Bool(_)))
+                    | (SimpleType::Natural, SimpleValue::Num(NumKind::Natural(_)))
+                    | (SimpleType::Integer, SimpleValue::Num(NumKind::Integer(_)))
+                    | (SimpleType::Double, SimpleValue::Num(NumKind::Double(_)))
+                    | (SimpleType::Text, SimpleValue::Text(_)) => None,
+                    (SimpleType::Optional(ty), SimpleValue::Optional(value)) => value.as_ref().and_then(|value| mismatch(ty, value)),
+                    (SimpleType::List(ty), SimpleValue::List(values)) => values.iter().enumerate()
+                        .find_map(|(index, value)| mismatch(ty, value).map(|(path, message)| (join(&::std::format!("[{}]", index), path), message))),
+                    (SimpleType::List(entry), SimpleValue::Record(entries)) if map_value_type(entry).is_some() => {
+                        let ty = map_value_type(entry)?;
+                        entries.iter().find_map(|(key, value)| mismatch(ty, value).map(|(path, message)| (join(&::std::format!("[{:?}]", key), path), message)))
+                    },
+                    (SimpleType::Record(fields), SimpleValue::Record(values)) => {
+                        for (name, ty) in fields {
+                            match values.get(name) {
+                                Some(value) => if let Some((path, message)) = mismatch(ty, value) {
+                                    return Some((join(name, path), message));
+                                },
+                                None => return Some((name.clone(), ::std::format!("expected {}, but the field is missing", ty))),
+                            }
+                        }
+                        values.keys().find(|name| !fields.contains_key(*name)).map(|name| (name.clone(), "unexpected field".into()))
+                    },
+                    (SimpleType::Union(alternatives), SimpleValue::Union(alternative, value)) => match (alternatives.get(alternative), value) {
+                        (None, _) => Some((alternative.clone(), "unexpected alternative".into())),
+                        (Some(Some(ty)), Some(value)) => mismatch(ty, value).map(|(path, message)| (join(alternative, path), message)),
+                        (Some(None), None) => None,
+                        (Some(Some(ty)), None) => Some((alternative.clone(), ::std::format!("expected a value of type {}", ty))),
+                        (Some(None), Some(value)) => Some((alternative.clone(), ::std::format!("expected no value, found {}", describe(value)))),
+                    },
+                    (ty, value) => Some((::std::string::String::new(), ::std::format!("expected {}, found {}", ty, describe(value)))),
+                }
+            }
+
+            // serde_dhall errors can't be wrapped, so the original message, with its file, line and column, follows on the next lines
+            match value.ok().and_then(|value| mismatch(ty, &value)) {
+                Some((path, message)) if path.is_empty() => <::serde_dhall::Error as ::serde::de::Error>::custom(::std::format!("{}\n{}", message, error)),
+                Some((path, message)) => <::serde_dhall::Error as ::serde::de::Error>::custom(::std::format!("{}: {}\n{}", path, message, error)),
+                // Not a type error (or the value can't be evaluated at all), so the original error is as good as it gets
+                None => error,
+            }
+        }
+        )
+    }
+
+    fn tokenize_struct_impl(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, r: AggregateTypeRecordFieldsIter<'a>, origin: &TypeOrigin) -> Result<TokenStream, Vec<Error>> {
         let mut tokens = TokenStream::new();
 
-        if self.options.struct_impl(ident.is_anonymous()).value {
-            for (field_name, field_type) in r {
-                let field_tokens = self.tokenize_field_impl(context_ident, Ident::new(field_name, Span::call_site()), field_type)?;
+        let clashes = self.struct_function_clashes(context_ident, ident, info, r.clone(), origin);
+        if !clashes.is_empty() {
+            return Err(clashes);
+        }
+
+        if self.options.struct_impl(ident.is_anonymous()) {
+            for (field_name, field_type) in r.clone() {
+                let field_tokens = self.tokenize_field_impl(context_ident, Ident::new(field_name, Span::call_site()), field_type, &origin.join(field_name))?;
                 tokens.extend(field_tokens);
             }
+        }
 
-            if !tokens.is_empty() {
-                let generic_parameter = Self::get_generic_parameter(0);
-                let generic = if info.is_open_generic() { quote!(<#generic_parameter>) } else { TokenStream::new() };
-                tokens = quote!(
-                impl #generic #ident #generic {
-                    #tokens
-                })
-            }
+        if self.options.struct_constructors.value {
+            tokens.extend(self.tokenize_struct_constructors(context_ident, info, r, origin)?);
+        }
+
+        if !tokens.is_empty() {
+            let generic_parameter = Self::get_generic_parameter(0);
+            let generic = if info.is_open_generic() { quote!(<#generic_parameter>) } else { TokenStream::new() };
+            tokens = quote!(
+            impl #generic #ident #generic {
+                #tokens
+            })
         }
 
         Ok(tokens)
     }
 
-    fn tokenize_enum_impl(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, u: AggregateTypeUnionAlternativesIter<'a>) -> Result<TokenStream, TokenStream> {
+    /// Errors for functions of the struct `ident` that would have the same name, as the accessors and setters are named after the fields
+    fn struct_function_clashes(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, r: AggregateTypeRecordFieldsIter<'a>, origin: &TypeOrigin) -> Vec<Error> {
+        let mut field_names = r.map(|(field_name, _)| field_name).collect::<Vec<_>>();
+        field_names.sort_unstable();
+
+        // (function name, what it was generated for, whether `struct_constructors` generated it)
+        let mut functions = Vec::new();
+        // The loaders and writers, which recursive types only have if their Dhall type can be unrolled (see `tokenize_recursive_writers`)
+        if !ident.is_anonymous() {
+            for loader in ["from_dhall_file", "from_dhall_file_with_imports", "from_dhall_str"] {
+                functions.push((loader.to_owned(), format!("the loader '{}'", loader), None, false));
+            }
+        }
+        let has_writers = !info.is_recursive() || (!ident.is_anonymous() && self.tokenize_recursive_dhall_type_members(context_ident, &ident.ident(), info, origin).is_ok_and(|dhall_type| dhall_type.is_some()));
+        if has_writers {
+            for writer in ["to_dhall_string", "write_dhall_file"] {
+                functions.push((writer.to_owned(), format!("the writer '{}'", writer), None, false));
+            }
+        }
+        if self.options.struct_constructors.value {
+            functions.push(("new".to_owned(), "the constructor".to_owned(), None, true));
+        }
+        for field_name in field_names {
+            let func_str = field_name.to_case(Case::Snake);
+            if self.options.struct_impl(ident.is_anonymous()) {
+                functions.push((func_str.clone(), format!("the accessor of field '{}'", field_name), Some(field_name), false));
+                functions.push((format!("{}_mut", func_str), format!("the mutable accessor of field '{}'", field_name), Some(field_name), false));
+            }
+            if self.options.struct_constructors.value {
+                functions.push((format!("with_{}", func_str), format!("the setter of field '{}'", field_name), Some(field_name), true));
+            }
+        }
+
+        let mut errors = Vec::new();
+        for (index, (func_name, generated_for, field_name, is_constructor)) in functions.iter().enumerate() {
+            for (_, other_generated_for, other_field_name, other_is_constructor) in functions[index + 1..].iter().filter(|(other_func_name, ..)| other_func_name == func_name) {
+                let message = format!("Struct '{}' can't have both {} and {}, which are both named '{}'", ident, generated_for, other_generated_for, func_name);
+                let error = match self.options.struct_constructors.span {
+                    Some(span) if *is_constructor || *other_is_constructor => Error::new_spanned(message, span),
+                    _ => Error::new(message),
+                };
+                let field_origin = field_name.or(*other_field_name).map(|field_name| origin.join(field_name)).unwrap_or_else(|| origin.clone());
+                errors.push(error.in_origin(&field_origin));
+            }
+        }
+        errors
+    }
+
+    /// `new`, taking every field in alphabetical order, and a consuming `with_*` setter for every field, which takes the value of `Option` fields
+    fn tokenize_struct_constructors(&self, context_ident: &Ident, info: AggregateTypeInfo<'a>, r: AggregateTypeRecordFieldsIter<'a>, origin: &TypeOrigin) -> Result<TokenStream, Error> {
+        let mut fields = r.collect::<Vec<_>>();
+        fields.sort_by_key(|(field_name, _)| *field_name);
+        let field_idents = fields.iter().map(|(field_name, _)| Ident::new(field_name, Span::call_site())).collect::<Vec<_>>();
+        let field_type_tokens = fields.iter()
+            .map(|(field_name, field_type)| self.tokenize_member_type(context_ident, field_type, &origin.join(field_name), false))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let phantom_field = if info.phantom_parameter().is_some() { quote!(_marker: ::core::marker::PhantomData,) } else { TokenStream::new() };
+
+        let setters = fields.iter().zip(field_idents.iter()).zip(field_type_tokens.iter()).map(|(((field_name, _), field_ident), field_type_tokens)| {
+            let doc = format!(" Set the `{}` field", field_name);
+            let option_doc = format!(" Set the `{}` field to `Some(value)`", field_name);
+            let with_func_ident = Ident::new(&format!("with_{}", field_name.to_case(Case::Snake)), Span::call_site());
+            match Self::option_inner_type(field_type_tokens) {
+                Some(inner_type_tokens) => quote!(
+                    #[doc = #option_doc]
+                    pub fn #with_func_ident(mut self, value: #inner_type_tokens) -> Self {
+                        self.#field_ident = ::core::option::Option::Some(value);
+                        self
+                    }
+                ),
+                None => quote!(
+                    #[doc = #doc]
+                    pub fn #with_func_ident(mut self, value: #field_type_tokens) -> Self {
+                        self.#field_ident = value;
+                        self
+                    }
+                ),
+            }
+        });
+
+        // Clippy warns about functions with more than 7 arguments, but `new` takes every field
+        let allow_arguments = if fields.len() > 7 { quote!(#[allow(clippy::too_many_arguments)]) } else { TokenStream::new() };
+
+        Ok(quote!(
+            /// Create a value from all of its fields
+            #allow_arguments
+            pub fn new(#(#field_idents: #field_type_tokens),*) -> Self {
+                Self {
+                    #(#field_idents,)*
+                    #phantom_field
+                }
+            }
+            #(#setters)*
+        ))
+    }
+
+    /// The type inside an `Option` type
+    fn option_inner_type(type_tokens: &TokenStream) -> Option<TokenStream> {
+        let path = match syn::parse2::<syn::Type>(type_tokens.clone()) {
+            Ok(syn::Type::Path(path)) if path.qself.is_none() => path.path,
+            _ => return None,
+        };
+        let segment = path.segments.last()?;
+        match &segment.arguments {
+            syn::PathArguments::AngleBracketed(arguments) if segment.ident == "Option" && arguments.args.len() == 1 => match &arguments.args[0] {
+                syn::GenericArgument::Type(ty) => Some(quote!(#ty)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn tokenize_enum_impl(&self, context_ident: &Ident, ident: IndexedIdent<'_>, info: AggregateTypeInfo<'a>, alternatives: &[(&'a str, Option<&'a SimpleType>)], origin: &TypeOrigin) -> Result<TokenStream, Error> {
         let mut tokens = TokenStream::new();
 
-        if self.options.enum_impl(ident.is_anonymous()).value {
-            for (variant_name, variant_type) in u {
-                let variant_tokens = self.tokenize_variant_impl(context_ident, Ident::new(variant_name, Span::call_site()), variant_type)?;
+        if self.options.enum_impl(ident.is_anonymous()) {
+            for &(variant_name, variant_type) in alternatives {
+                let variant_tokens = self.tokenize_variant_impl(context_ident, Ident::new(variant_name, Span::call_site()), variant_type, &origin.join(variant_name))?;
                 let new_tokens = quote!(#variant_tokens);
                 tokens.extend(new_tokens);
             }
@@ -251,27 +1797,193 @@ impl<'a> TypeGen<'a> {
         Ok(tokens)
     }
 
-    pub fn tokenize(self) -> Result<TokenStream, TokenStream> {
-        fn tokenize_internal(tg: &TypeGen) -> Result<TokenStream, TokenStream> {
-            let mut tokens = TokenStream::new();
+    /// A type alias for a type that is not a record or union or for an instantiation of a generic type, or a `#[serde(transparent)]` newtype with `alias_newtypes`
+    fn tokenize_alias(&self, alias: &TypeAlias<'a>) -> Result<TokenStream, Error> {
+        let ident = alias.ident();
+        let origin = alias.origin();
+        let is_open_generic = simple_type_is_open_generic(alias.ty());
+        let generic_parameter = Self::get_generic_parameter(0);
+        let generic = if is_open_generic { quote!(<#generic_parameter>) } else { TokenStream::new() };
+        let tokenize_target = |with_pointers: bool| -> Result<TokenStream, Error> {
+            Ok(match alias.instantiation() {
+                // Resolving the instantiation normally would find this alias, so name the generic type directly
+                Some(ty) => {
+                    let generic_type = self.type_collection.get_by_structure(None, ty)?
+                        .ok_or_else(|| Error::new(format!("Internal error: type alias '{}' is not an instantiation of a generic type", ident)))?;
+                    let generic_ident = generic_type.ident();
+                    let parameter = generic_type.type_parameter().map(|parameter| self.tokenize_type_ident_internal(ident, parameter, origin, with_pointers)).transpose()?;
+                    quote!(#generic_ident<#parameter>)
+                },
+                None => self.tokenize_type_ident_internal(ident, alias.ty(), origin, with_pointers)?,
+            })
+        };
+        let target = tokenize_target(true)?;
+        let type_visibility = self.options.type_visibility.value;
+        let doc = Self::tokenize_doc(&[
+            Self::source_line(origin),
+            String::new(),
+            format!("Dhall type: `{}{}`", if is_open_generic { "\\(T : Type) -> " } else { "" }, DhallTypeDisplay(alias.ty())),
+        ]);
+
+        if !self.options.alias_newtypes.value {
+            return Ok(quote!(
+            #doc
+            #type_visibility type #ident #generic = #target;
+            ));
+        }
+
+        let field_visibility = self.options.field_visibility.value;
+        let is_recursive = simple_type_is_recursive(alias.ty());
+        let static_type_generic = if is_open_generic { quote!(<#generic_parameter: ::serde_dhall::StaticType>) } else { TokenStream::new() };
+        let indexed_ident = IndexedIdent::new_named(ident);
+        let loader_impl = Self::tokenize_loaders(indexed_ident, is_open_generic, is_recursive || self.simple_type_has_catch_all_variant(alias.ty()));
+        let static_target = tokenize_target(false)?;
+        let refers_to_mapped_type = match alias.instantiation() {
+            Some(ty) => match self.type_collection.get_by_structure(None, ty)?.and_then(|generic_type| generic_type.type_parameter()) {
+                Some(parameter) => self.refers_to_mapped_type(ident, parameter, origin)?,
+                None => false,
+            },
+            None => self.refers_to_mapped_type(ident, alias.ty(), origin)?,
+        };
+        let static_type = if refers_to_mapped_type {
+            Self::tokenize_simple_type(alias.ty())
+        } else {
+            quote!(<#static_target as ::serde_dhall::StaticType>::static_type())
+        };
+        let (static_type_impl, writer_impl) = if is_recursive {
+            (TokenStream::new(), TokenStream::new())
+        } else {
+            (quote!(
+            impl #static_type_generic ::serde_dhall::StaticType for #ident #generic {
+                fn static_type() -> ::serde_dhall::SimpleType {
+                    #static_type
+                }
+            }
+            ), Self::tokenize_writers(indexed_ident, is_open_generic))
+        };
+        Ok(quote!(
+        #doc
+        #[derive(Debug, Clone, Eq, PartialEq, Hash, ::serde::Serialize, ::serde::Deserialize)]
+        #[serde(transparent)]
+        #type_visibility struct #ident #generic(#field_visibility #target);
+        impl #generic ::core::convert::From<#target> for #ident #generic {
+            fn from(value: #target) -> Self {
+                Self(value)
+            }
+        }
+        impl #generic ::core::convert::From<#ident #generic> for #target {
+            fn from(value: #ident #generic) -> Self {
+                value.0
+            }
+        }
+        impl #generic ::core::ops::Deref for #ident #generic {
+            type Target = #target;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        #static_type_impl
+        #loader_impl
+        #writer_impl
+        ))
+    }
+
+    /// Generate all types that could be generated, along with the errors for those that could not
+    pub fn tokenize(mut self) -> (TokenStream, Vec<Error>) {
+        fn tokenize_internal(tg: &TypeGen) -> (TokenStream, Vec<Error>) {
+            let mut tokens = TypeGen::tokenize_load_error();
+            let mut errors = Vec::new();
+            let rust_name = |st: &SimpleType| tg.named_type_rust_name(st);
+            let named_types = NamedTypes { rust_name: &rust_name, names: tg.named_type_rust_names() };
         
             for nt in tg.type_collection.iter_with_context(None) {
-                let new_tokens = tg.tokenize_type(None, nt)?;
-                tokens.extend(new_tokens);
+                // Types mapped to an existing Rust type aren't generated, nor are the anonymous types they contain
+                if !nt.ident().is_anonymous() && tg.type_collection.mapped_type(nt.ident().base_ident()).is_some() {
+                    let ident = nt.ident().to_string();
+                    match tg.options.hand_written_type(&ident) {
+                        Some(hand_written) if tg.options.mapped_type(&ident).is_none() && tg.options.check_hand_written_types.value => match nt.ty().info() {
+                            Ok(info) => errors.extend(hand_written.check(info, nt.origin(), Some(&named_types))),
+                            Err(e) => errors.push(e.in_origin(nt.origin())),
+                        },
+                        _ => {},
+                    }
+                    tg.used_type_map.borrow_mut().insert(ident);
+                    continue;
+                }
+
+                match tg.tokenize_type(None, nt) {
+                    Ok(new_tokens) => tokens.extend(new_tokens),
+                    Err(new_errors) => errors.extend(new_errors),
+                }
 
                 if nt.ident().is_anonymous() {
-                    panic!("Orphaned anonymous type '{}'", nt.ident().ident());
+                    continue;
                 }
 
                 for anon_nt in tg.type_collection.iter_anonymous_types(nt.ident().base_ident()) {
-                    let new_tokens = tg.tokenize_type(Some(nt.ident().base_ident()), anon_nt)?;
-                    tokens.extend(new_tokens);
+                    match tg.tokenize_type(Some(nt.ident().base_ident()), anon_nt) {
+                        Ok(new_tokens) => tokens.extend(new_tokens),
+                        Err(new_errors) => errors.extend(new_errors),
+                    }
+                }
+            }
+
+            tokens.extend(tg.tokenize_record_conversions());
+
+            // Aliases can be created while tokenizing the types using them, so these go last
+            for alias in tg.type_collection.iter_aliases() {
+                if tg.type_collection.mapped_type(alias.ident()).is_some() {
+                    tg.used_type_map.borrow_mut().insert(alias.ident().to_string());
+                    continue;
+                }
+                match tg.tokenize_alias(alias) {
+                    Ok(new_tokens) => tokens.extend(new_tokens),
+                    Err(new_error) => errors.push(new_error.in_origin(alias.origin())),
+                }
+            }
+
+            // Every pointer option should have matched a type or member
+            let used_pointers = tg.used_pointers.borrow();
+            for (path, pointer) in tg.options.pointers.iter().filter(|(path, _)| !used_pointers.contains(path)) {
+                let message = format!("'{}' does not name a generated type, or a field or alternative of one", path);
+                errors.push(match pointer.span {
+                    Some(span) => Error::new_spanned(message, span),
+                    None => Error::new(message),
+                });
+            }
+
+            let used_type_map = tg.used_type_map.borrow();
+            for (path, mapped) in tg.options.type_map.iter().filter(|(path, _)| !used_type_map.contains(path)) {
+                let message = format!("'{}' does not name a generated type or type alias, or a field or alternative of one", path);
+                errors.push(match mapped.span {
+                    Some(span) => Error::new_spanned(message, span),
+                    None => Error::new(message),
+                });
+            }
+
+            if tg.has_catch_all_variants.get() {
+                tokens.extend(tg.tokenize_unknown_alternative());
+            }
+            // Every type named by `catch_all_variants` should be a generated enum
+            for name in tg.options.catch_all_types.iter() {
+                let is_enum = tg.type_collection.get_by_ident(&Ident::new(&name.value, Span::call_site()))
+                    .is_some_and(|nt| nt.ty().info().is_ok_and(|info| matches!(info.kind(), AggregateKind::Union)) && tg.type_collection.mapped_type(nt.ident().base_ident()).is_none());
+                if !is_enum {
+                    let message = format!("'{}' does not name a generated enum", name.value);
+                    errors.push(match name.span {
+                        Some(span) => Error::new_spanned(message, span),
+                        None => Error::new(message),
+                    });
                 }
             }
 
-            Ok(tokens)
+            (tokens, errors)
         }
-        
-        tokenize_internal(&self)
+
+        let mut errors = self.add_closed_file_types();
+        let (tokens, tokenize_errors) = tokenize_internal(&self);
+        errors.extend(tokenize_errors);
+        (tokens, errors)
     }
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use std::fmt;
+use std::rc::Rc;
+
+/// The location in the Dhall source where a type was first encountered: a file and the path of fields/alternatives within it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeOrigin {
+    file: Rc<str>,
+    field_path: Vec<Rc<str>>,
+}
+
+impl TypeOrigin {
+    pub fn new(file: &str) -> Self {
+        Self {
+            file: file.trim_start_matches("./").into(),
+            field_path: Vec::new(),
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The dot-separated path of fields and alternatives within the file, empty for the root of the file
+    pub fn field_path(&self) -> String {
+        self.field_path.join(".")
+    }
+
+    /// The dot-separated path of fields and alternatives from `base` to this origin, if this origin is nested within `base`
+    pub fn field_path_from(&self, base: &TypeOrigin) -> Option<String> {
+        if self.file == base.file && self.field_path.len() > base.field_path.len() && self.field_path.starts_with(&base.field_path) {
+            Some(self.field_path[base.field_path.len()..].join("."))
+        } else {
+            None
+        }
+    }
+
+    /// The name of the innermost field or alternative, if this is not the root of a file
+    pub fn member_name(&self) -> Option<&str> {
+        self.field_path.last().map(AsRef::as_ref)
+    }
+
+    /// The origin of a field or alternative of the type at this origin
+    pub fn join(&self, member_name: &str) -> Self {
+        let mut field_path = self.field_path.clone();
+        field_path.push(member_name.into());
+        Self {
+            file: self.file.clone(),
+            field_path,
+        }
+    }
+}
+
+impl fmt::Display for TypeOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.file)?;
+        for (index, member_name) in self.field_path.iter().enumerate() {
+            f.write_str(if index == 0 { ": " } else { "." })?;
+            f.write_str(member_name)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+
+use proc_macro2::{Span, TokenStream};
+
+use crate::type_origin::TypeOrigin;
+
+use super::create_spanned_error;
+
+/// An error encountered while generating types, optionally tied to a span in the macro input and a location in the Dhall source
+#[derive(Debug, Clone)]
+pub struct Error {
+    message: String,
+    span: Option<Span>,
+    origin: Option<TypeOrigin>,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
+            origin: None,
+        }
+    }
+
+    pub fn new_spanned(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span: Some(span),
+            origin: None,
+        }
+    }
+
+    /// Attach the Dhall location the error occurred at, unless a more specific location is already known
+    pub fn in_origin(mut self, origin: &TypeOrigin) -> Self {
+        if self.origin.is_none() {
+            self.origin = Some(origin.clone());
+        }
+        self
+    }
+
+    /// Create a `compile_error!` invocation for the error, pointing at `default_span` if the error has no span of its own
+    pub fn to_compile_error(&self, default_span: Span) -> TokenStream {
+        create_spanned_error(&self.to_string(), self.span.unwrap_or(default_span))
+    }
+}
+
+impl From<Error> for Vec<Error> {
+    fn from(error: Error) -> Self {
+        vec![error]
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(origin) = &self.origin {
+            write!(f, "{}: {}", origin, self.message)
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+/// Collect the values of all successful results, or every error if any result failed, so that all errors can be reported at once
+pub fn collect_results<T>(results: impl IntoIterator<Item=Result<T, Error>>) -> Result<Vec<T>, Vec<Error>> {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
@@ -1,16 +1,14 @@
+mod error;
 mod push_cd;
 mod single;
 
 use proc_macro2::{TokenStream, Span};
-use quote::{quote, quote_spanned};
+use quote::quote_spanned;
 
+pub use error::*;
 pub use push_cd::*;
 pub use single::*;
 
-pub fn create_error(msg: &str) -> TokenStream {
-    quote!(std::compile_error!(#msg);)
-}
-
 pub fn create_spanned_error(msg: &str, span: Span) -> TokenStream {
     quote_spanned!(span => std::compile_error!(#msg);)
-}
\ No newline at end of file
+}
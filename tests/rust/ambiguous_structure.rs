@@ -0,0 +1,4 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/ambiguous/")]
+mod dhall { }
+
+fn main() { }
@@ -0,0 +1,52 @@
+use serde_dhall::{NumKind, SimpleValue, StaticType};
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/catch_all/", catch_all_variants = "Shape")]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/catch_all/", catch_all_variants = true)]
+mod all { }
+
+use dhall::{Drawing, Shape, PublishStatus, UnknownAlternative};
+
+fn main() {
+    // A newer version of the Dhall types with more alternatives
+    let drawing = Drawing::from_dhall_str(r#"
+        let Shape = < Point | Circle: { radius: Natural } | Square: { side: Natural } | Line >
+        in {
+            name = "sketch",
+            shapes = [ Shape.Point, Shape.Square { side = 2 }, Shape.Line, Shape.Circle { radius = 1 } ],
+            layer = < Background | Foreground >.Foreground,
+            status = < Draft | Published: Natural | Archived: Text >.Archived "2024",
+        }
+    "#).unwrap();
+    assert_eq!(drawing.shapes.len(), 4);
+    assert_eq!(drawing.shapes[0], Shape::Point);
+    // The catch-all variant keeps the name of the alternative, and its value if it has one
+    let side = SimpleValue::Record(vec![("side".to_string(), SimpleValue::Num(NumKind::Natural(2)))].into_iter().collect());
+    assert_eq!(drawing.shapes[1], Shape::Unknown(UnknownAlternative { name: "Square".to_string(), value: Some(side) }));
+    assert_eq!(drawing.shapes[2], Shape::Unknown(UnknownAlternative { name: "Line".to_string(), value: None }));
+    assert!(matches!(&drawing.shapes[3], Shape::Circle(circle) if circle.radius == 1));
+    // Unions marked with `env:rust_catch_all` get one without the option, also inside a name given with metadata
+    assert_eq!(drawing.status, PublishStatus::Unknown(UnknownAlternative { name: "Archived".to_string(), value: Some(SimpleValue::Text("2024".to_string())) }));
+
+    // The catch-all variant isn't part of the Dhall type
+    assert_eq!(Shape::static_type(), serde_dhall::from_file("../../../tests/dhall/catch_all/shape.dhall").parse::<serde_dhall::SimpleType>().unwrap());
+    assert_eq!(PublishStatus::static_type(), serde_dhall::from_file("../../../tests/dhall/catch_all/status.dhall").parse::<serde_dhall::SimpleType>().unwrap());
+    let known = Drawing { name: "known".to_string(), shapes: vec![Shape::Point], layer: drawing.layer.clone(), status: PublishStatus::Published(3) };
+    assert_eq!(Drawing::from_dhall_str(&known.to_dhall_string().unwrap()).unwrap(), known);
+    assert!(drawing.shapes[2].to_dhall_string().is_err());
+    assert!(serde_dhall::serialize(&drawing.shapes[2]).to_string().is_err());
+
+    // Every enum gets one, including anonymous ones
+    let drawing = all::Drawing::from_dhall_str(r#"
+        {
+            name = "sketch",
+            shapes = [] : List < Point | Circle: { radius: Natural } >,
+            layer = < Background | Foreground | Overlay >.Overlay,
+            status = < Draft | Published: Natural >.Draft,
+        }
+    "#).unwrap();
+    assert_eq!(drawing.layer, all::DrawingAnon0::Unknown(all::UnknownAlternative { name: "Overlay".to_string(), value: None }));
+    assert_eq!(drawing.layer.as_str(), "Unknown");
+    assert_eq!(drawing.status, all::PublishStatus::Draft);
+}
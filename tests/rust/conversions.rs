@@ -0,0 +1,34 @@
+use std::convert::TryFrom;
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/derived/")]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/")]
+mod schema { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/recursive/")]
+mod recursive { }
+
+use dhall::{Shape, ShapeAnon0};
+
+fn main() {
+    // Payloads convert into the alternative holding them
+    assert_eq!(Shape::from(2), Shape::Circle(2));
+    let square: Shape = ShapeAnon0 { side: 3 }.into();
+    assert_eq!(square, Shape::Square(ShapeAnon0 { side: 3 }));
+
+    // And back, giving back the enum for other alternatives
+    assert_eq!(u64::try_from(Shape::Circle(2)), Ok(2));
+    assert_eq!(ShapeAnon0::try_from(square), Ok(ShapeAnon0 { side: 3 }));
+    assert_eq!(u64::try_from(Shape::Point), Err(Shape::Point));
+
+    // Pointers to a recursive enum only convert into it
+    let negate = recursive::Expr::from(Box::new(recursive::Expr::from(1)));
+    assert_eq!(negate, recursive::Expr::Negate(Box::new(recursive::Expr::Literal(1))));
+
+    // Records with the same Dhall type but different names convert into each other
+    let foo = schema::Foo { name: "foo".to_owned() };
+    let bar = schema::Bar::from(foo.clone());
+    assert_eq!(bar.name, "foo");
+    assert_eq!(schema::Foo::from(bar), foo);
+}
@@ -0,0 +1,15 @@
+// The .dhall files were written by the types deriving `DhallSchema` in tests/rust/dhall_schema.rs
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/derived/")]
+mod dhall { }
+
+fn main() {
+    let settings = dhall::Settings::from_dhall_str("(../../../tests/dhall/derived/settings.dhall)::{ volume = 11 }").unwrap();
+    assert_eq!(settings, dhall::Settings { volume: 11, theme: None, tags: Vec::new() });
+
+    let drawing = dhall::Drawing {
+        shapes: vec![dhall::Shape::Circle(1), dhall::Shape::Point],
+        origin: dhall::Pair { first: -1, second: 1 },
+        settings,
+    };
+    assert_eq!(dhall::Drawing::from_dhall_str(&drawing.to_dhall_string().unwrap()).unwrap(), drawing);
+}
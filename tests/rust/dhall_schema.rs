@@ -0,0 +1,100 @@
+use serde_dhall_typegen::DhallSchema;
+
+#[derive(DhallSchema)]
+pub struct Pair<T> {
+    pub first: T,
+    pub second: T,
+}
+
+#[derive(DhallSchema)]
+pub enum Shape {
+    Circle(u64),
+    Square { side: u64 },
+    Point,
+}
+
+#[derive(Default, serde::Serialize, serde_dhall::StaticType, DhallSchema)]
+#[dhall_schema(default)]
+pub struct Settings {
+    pub volume: u64,
+    pub theme: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(DhallSchema)]
+pub struct Drawing {
+    pub shapes: Vec<Shape>,
+    pub origin: Pair<i64>,
+    pub settings: Settings,
+}
+
+// Names follow serde, so the schema describes the values the type serializes to
+#[derive(serde::Serialize, DhallSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    #[serde(rename = "user")]
+    pub user_name: String,
+    pub login_count: u64,
+    pub role: Role,
+    #[serde(skip)]
+    pub cache: Vec<u8>,
+}
+
+#[derive(serde::Serialize, DhallSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    ReadOnly(u64),
+    #[serde(skip)]
+    #[allow(dead_code)]
+    Internal,
+}
+
+// Like serde, `rename_all` splits variants before each uppercase character only, not at digits or within acronyms
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, DhallSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    Level2,
+    HTTPServer,
+}
+
+const DERIVED_PATH: &str = "../../../tests/dhall/derived/";
+
+fn main() {
+    assert_eq!(Pair::<u64>::dhall_type(), "\\(T : Type) -> { first : T, second : T }");
+    assert_eq!(Shape::dhall_type(), "< Circle : Natural | Square : { side : Natural } | Point >");
+    assert_eq!(Settings::dhall_type_import(), "(./settings.dhall).Type");
+    assert_eq!(Drawing::dhall_type(), "{ shapes : List ./shape.dhall, origin : ./pair.dhall Integer, settings : (./settings.dhall).Type }");
+
+    assert_eq!(Account::dhall_type(), "{ user : Text, loginCount : Natural, role : ./role.dhall }");
+    assert_eq!(Role::dhall_type(), "< admin | read_only : Natural >");
+    let account = Account { user_name: "ada".to_owned(), login_count: 3, role: Role::ReadOnly(1843), cache: vec![1] };
+    let account_type = serde_dhall::from_str(&Account::dhall_type().replace("./role.dhall", &Role::dhall_type())).parse::<serde_dhall::SimpleType>().unwrap();
+    let serialized = serde_dhall::serialize(&account).type_annotation(&account_type).to_string().unwrap();
+    assert_eq!(serde_dhall::from_str(&serialized).type_annotation(&account_type).parse::<serde_dhall::SimpleValue>().unwrap(), serde_dhall::from_str("{ user = \"ada\", loginCount = 3, role = < admin | read_only : Natural >.read_only 1843 }").parse::<serde_dhall::SimpleValue>().unwrap());
+
+    assert_eq!(Protocol::dhall_type(), "< level2 | h_t_t_p_server >");
+    let protocol_type = serde_dhall::from_str(&Protocol::dhall_type()).parse::<serde_dhall::SimpleType>().unwrap();
+    for protocol in [Protocol::Level2, Protocol::HTTPServer] {
+        let serialized = serde_dhall::serialize(&protocol).type_annotation(&protocol_type).to_string().unwrap();
+        assert_eq!(serde_dhall::from_str(&serialized).type_annotation(&protocol_type).parse::<Protocol>().unwrap(), protocol);
+    }
+
+    // The checked in files are regenerated into Rust types by tests/rust/derived_types.rs
+    for (file_name, schema) in [
+        (Pair::<u64>::dhall_schema_file_name(), Pair::<u64>::dhall_schema()),
+        (Shape::dhall_schema_file_name(), Shape::dhall_schema()),
+        (Settings::dhall_schema_file_name(), Settings::dhall_schema()),
+        (Drawing::dhall_schema_file_name(), Drawing::dhall_schema()),
+    ] {
+        let expected = std::fs::read_to_string(format!("{}{}", DERIVED_PATH, file_name)).unwrap();
+        assert_eq!(schema.unwrap() + "\n", expected, "{}", file_name);
+    }
+
+    let directory = std::env::temp_dir().join("serde_dhall_typegen_dhall_schema");
+    std::fs::create_dir_all(&directory).unwrap();
+    Settings::write_dhall_schema(&directory).unwrap();
+    let settings = std::fs::read_to_string(directory.join("settings.dhall")).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(settings, Settings::dhall_schema().unwrap() + "\n");
+}
@@ -0,0 +1,11 @@
+use std::collections::HashMap;
+
+use serde_dhall_typegen::DhallSchema;
+
+// Maps don't read back as maps, neither from the records serde_dhall writes nor through `dhall_types`
+#[derive(DhallSchema)]
+pub struct Labels {
+    pub labels: HashMap<String, String>,
+}
+
+fn main() {}
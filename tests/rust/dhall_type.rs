@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+use serde_dhall_typegen::dhall_type;
+
+#[dhall_type("../../../tests/dhall/hand_written/palette.dhall")]
+#[derive(Debug, Deserialize)]
+struct Palette {
+    name: String,
+    primary: Color,
+    #[serde(rename = "accents")]
+    accent_colors: Vec<Color>,
+    mode: Mode,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    cached_hex: Option<String>,
+}
+
+#[dhall_type("../../../tests/dhall/hand_written/color.dhall")]
+#[derive(Debug, PartialEq, Deserialize)]
+struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+#[dhall_type("../../../tests/dhall/hand_written/mode.dhall")]
+#[derive(Debug, PartialEq, Deserialize)]
+enum Mode {
+    Light,
+    Dark,
+    Custom(String),
+}
+
+// Names follow serde's attributes on the type as well as on its fields and variants
+#[dhall_type("../../../tests/dhall/hand_written/account.dhall")]
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Account {
+    user_name: String,
+    #[serde(alias = "nick")]
+    nickname: Option<String>,
+    #[serde(rename(deserialize = "user_id"))]
+    id: u64,
+    status: Status,
+}
+
+#[dhall_type("../../../tests/dhall/hand_written/status.dhall")]
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Active,
+    Suspended,
+}
+
+// Like serde, `rename_all` splits variants before each uppercase character only, not at digits or within acronyms
+#[dhall_type("../../../tests/dhall/renamed/protocol.dhall")]
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Protocol {
+    Level2,
+    HTTPServer,
+}
+
+// Schemas are checked against their `Type`
+#[dhall_type("../../../tests/dhall/schema/person.dhall")]
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Person {
+    name: String,
+    age_range: AgeRange,
+    occupation: Option<Occupation>,
+}
+
+#[derive(Debug, Deserialize)]
+enum AgeRange { Baby, Toddler, Child, Teenager, Adult, Senior }
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Occupation {
+    title: String,
+    salary: u64,
+}
+
+// Maps are read from `toMap` style lists and from records whose fields all have the type of the values
+#[dhall_type("../../../tests/dhall/maps/settings.dhall")]
+#[derive(Debug, Deserialize)]
+struct Settings {
+    limits: HashMap<String, u64>,
+    labels: BTreeMap<String, String>,
+}
+
+fn main() {
+    // Nothing is generated, the annotated types are used as they are
+    let palette: Palette = serde_dhall::from_str(r#"
+        let Mode = ../../../tests/dhall/hand_written/mode.dhall
+        in { name = "sunset", primary = { r = 255, g = 128, b = 0 }, accents = [] : List { r: Natural, g: Natural, b: Natural }, mode = Mode.Dark }
+    "#).parse().unwrap();
+    assert_eq!(palette.name, "sunset");
+    assert_eq!(palette.primary, Color { r: 255, g: 128, b: 0 });
+    assert!(palette.accent_colors.is_empty());
+    assert_eq!(palette.mode, Mode::Dark);
+
+    let account: Account = serde_dhall::from_str(r#"
+        { userName = "ada", nick = None Text, user_id = 1815, status = < active | suspended >.suspended }
+    "#).parse().unwrap();
+    assert_eq!(account, Account { user_name: "ada".to_owned(), nickname: None, id: 1815, status: Status::Suspended });
+
+    let protocols: Vec<Protocol> = serde_dhall::from_str(r#"
+        let Protocol = ../../../tests/dhall/renamed/protocol.dhall
+        in [ Protocol.level2, Protocol.h_t_t_p_server ]
+    "#).parse().unwrap();
+    assert_eq!(protocols, vec![Protocol::Level2, Protocol::HTTPServer]);
+
+    let settings: Settings = serde_dhall::from_str(r#"
+        { limits = toMap { requests = 100, users = 5 }, labels = { env = "prod", region = "eu" } }
+    "#).parse().unwrap();
+    assert_eq!(settings.limits["requests"], 100);
+    assert_eq!(settings.labels["region"], "eu");
+}
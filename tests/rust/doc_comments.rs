@@ -0,0 +1,14 @@
+//! Every generated item, including fields, variants and functions, must have docs
+#![deny(missing_docs)]
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/docs/", named_impl = true, anonymous_impl = true, struct_constructors = true)]
+/// The types generated from the commented Dhall files
+pub mod dhall { }
+
+fn main() {
+    let team = dhall::Team::from_dhall_str(r#"
+        let Employee = ../../../tests/dhall/docs/employee.dhall
+        in { members = [] : List Employee, office = "Remote" }
+    "#).unwrap();
+    println!("{}", team.office);
+}
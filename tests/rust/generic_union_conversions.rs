@@ -0,0 +1,14 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/generic_union/")]
+mod dhall { }
+
+use dhall::{Settings, Value};
+
+fn main() {
+    // `Custom: T` and `Count: Natural` have the same type in `Value Natural`, so neither converts into the enum
+    let settings = Settings::from_dhall_str(r#"
+        let Value = ../../../tests/dhall/generic_union/value.dhall
+        in { values = [ (Value Natural).Custom 1, (Value Natural).Count 2 ], labels = [ (Value Text).Custom "a", (Value Text).Empty ] }
+    "#).unwrap();
+    assert_eq!(settings.values, vec![Value::Custom(1), Value::Count(2)]);
+    assert_eq!(settings.labels, vec![Value::Custom("a".to_owned()), Value::Empty]);
+}
@@ -0,0 +1,20 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/hand_written/")]
+mod dhall {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    pub struct Color {
+        pub r: u8,
+        pub g: String,
+        pub alpha: u8,
+    }
+
+    // Fields referring to named records and unions are checked against their Rust types
+    #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    pub struct Palette {
+        pub name: Color,
+        pub primary: Mode,
+        pub accents: std::collections::HashMap<String, Color>,
+        pub mode: Mode,
+    }
+}
+
+fn main() {}
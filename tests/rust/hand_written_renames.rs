@@ -0,0 +1,23 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/renamed/")]
+mod dhall {
+    // Like serde, `rename_all` splits variants before each uppercase character only, not at digits or within acronyms
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Protocol {
+        Level2,
+        HTTPServer,
+    }
+}
+
+use dhall::{Endpoint, Protocol};
+
+fn main() {
+    let endpoint = Endpoint::from_dhall_str(r#"
+        let Protocol = ../../../tests/dhall/renamed/protocol.dhall
+        in { host = "localhost", protocol = Protocol.h_t_t_p_server }
+    "#).unwrap();
+    assert_eq!(endpoint.protocol, Protocol::HTTPServer);
+
+    let level2: Protocol = serde_dhall::from_str("< level2 | h_t_t_p_server >.level2").parse().unwrap();
+    assert_eq!(level2, Protocol::Level2);
+}
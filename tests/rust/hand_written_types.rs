@@ -0,0 +1,74 @@
+use serde_dhall::StaticType;
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/hand_written/")]
+mod dhall {
+    /// Written by hand to use smaller integers and add methods, instead of generating `Color` from color.dhall
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    pub struct Color {
+        pub r: u8,
+        pub g: u8,
+        pub b: u8,
+    }
+
+    impl Color {
+        pub fn to_hex(self) -> String {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    pub enum Mode {
+        Light,
+        Dark,
+        #[serde(rename = "Custom")]
+        Other(String),
+    }
+
+    /// Checked by the names serde deserializes its fields and variants from
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Account {
+        pub user_name: String,
+        #[serde(alias = "nick")]
+        pub nickname: Option<String>,
+        #[serde(rename(deserialize = "user_id"))]
+        pub id: u64,
+        pub status: Status,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Status {
+        Active,
+        Suspended,
+    }
+}
+
+use dhall::{Account, Color, Mode, Palette, Status};
+
+fn main() {
+    let palette = Palette::from_dhall_str(r#"
+        let Mode = ../../../tests/dhall/hand_written/mode.dhall
+        in {
+            name = "sunset",
+            primary = { r = 255, g = 128, b = 0 },
+            accents = [ { r = 16, g = 32, b = 64 } ],
+            mode = Mode.Custom "dusk",
+        }
+    "#).unwrap();
+
+    // The generated `Palette` refers to the hand-written types
+    let primary: Color = palette.primary;
+    assert_eq!(primary.to_hex(), "#ff8000");
+    assert_eq!(palette.accents, vec![Color { r: 16, g: 32, b: 64 }]);
+    assert_eq!(palette.mode, Mode::Other("dusk".to_string()));
+
+    // Hand-written types needn't implement `StaticType`, the Dhall type still comes from the .dhall files
+    assert_eq!(Palette::static_type(), serde_dhall::from_file("../../../tests/dhall/hand_written/palette.dhall").parse::<serde_dhall::SimpleType>().unwrap());
+    assert_eq!(Palette::from_dhall_str(&palette.to_dhall_string().unwrap()).unwrap(), palette);
+
+    let account: Account = serde_dhall::from_str(r#"
+        { userName = "ada", nick = Some "countess", user_id = 1815, status = < active | suspended >.active }
+    "#).parse().unwrap();
+    assert_eq!(account, Account { user_name: "ada".to_owned(), nickname: Some("countess".to_owned()), id: 1815, status: Status::Active });
+}
@@ -0,0 +1,25 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/inline/", inline_variant_records = true, enum_impl = true)]
+mod dhall { }
+
+fn main() {
+    let drawing = dhall::Drawing::from_dhall_str(r#"
+        let Shape = ../../../tests/dhall/inline/shape.dhall
+        let Maybe = ../../../tests/dhall/inline/maybe.dhall
+        in {
+            shapes = [ Shape.Circle { radius = 2 }, Shape.Rect { width = 3, height = 4 }, Shape.Point ],
+            center = (Maybe Natural).Just { value = 1 },
+        }
+    "#).unwrap();
+
+    match &drawing.shapes[0] {
+        dhall::Shape::Circle { radius } => assert_eq!(*radius, 2),
+        _ => panic!("Expected a circle"),
+    }
+    // Fields are accessed in alphabetical order
+    assert_eq!(drawing.shapes[1].rect(), Some((&4, &3)));
+    assert!(drawing.shapes[1].is_rect());
+    assert!(drawing.shapes[2].is_point());
+    assert_eq!(drawing.center.clone().into_just(), Ok((1,)));
+
+    assert_eq!(dhall::Drawing::from_dhall_str(&drawing.to_dhall_string().unwrap()).unwrap(), drawing);
+}
@@ -0,0 +1,17 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/instantiations/")]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/instantiations/", alias_newtypes = true)]
+mod newtypes { }
+
+fn main() {
+    let quantity: dhall::TextQuantity = dhall::Quantity { item: String::from("eggs"), quantity: 12 };
+    let list = dhall::ShoppingList { items: vec![quantity] };
+    assert_eq!(list.items[0].item, "eggs");
+
+    // `Quantity Text` is named after text_quantity.dhall wherever it appears
+    let list = newtypes::ShoppingList::from_dhall_file("../../../tests/dhall/shopping_list.dhall").unwrap();
+    let first: &newtypes::TextQuantity = &list.items[0];
+    let first: newtypes::Quantity<String> = first.clone().into();
+    assert_eq!(newtypes::TextQuantity::from(first.clone()).to_dhall_string().unwrap(), first.to_dhall_string().unwrap());
+}
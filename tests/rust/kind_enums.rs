@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/inline/", inline_variant_records = true, kind_enums = true)]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/catch_all/", catch_all_variants = true, kind_enums = true)]
+mod catch_all { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/nullary/", kind_enums = true)]
+mod nullary { }
+
+use dhall::{Maybe, MaybeKind, Shape, ShapeKind};
+use nullary::Weekday;
+
+fn main() {
+    let drawing = dhall::Drawing::from_dhall_str(r#"
+        let Shape = ../../../tests/dhall/inline/shape.dhall
+        let Maybe = ../../../tests/dhall/inline/maybe.dhall
+        in {
+            shapes = [ Shape.Circle { radius = 2 }, Shape.Rect { width = 3, height = 4 }, Shape.Point, Shape.Circle { radius = 1 } ],
+            center = (Maybe Natural).Just { value = 1 },
+        }
+    "#).unwrap();
+
+    // The kind of every variant, whatever its fields
+    let kinds = drawing.shapes.iter().map(Shape::kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![ShapeKind::Circle, ShapeKind::Rect, ShapeKind::Point, ShapeKind::Circle]);
+    assert_eq!(drawing.shapes[1].name(), "Rect");
+    assert_eq!(drawing.center.kind(), MaybeKind::Just);
+    assert_eq!(Maybe::<u64>::Nothing.name(), "Nothing");
+
+    // Kinds are nullary enums, usable as keys
+    let mut counts = HashMap::new();
+    for shape in drawing.shapes.iter() {
+        *counts.entry(shape.kind()).or_insert(0) += 1;
+    }
+    assert_eq!(counts[&ShapeKind::Circle], 2);
+    assert_eq!(ShapeKind::ALL, [ShapeKind::Circle, ShapeKind::Point, ShapeKind::Rect]);
+    assert_eq!("Point".parse::<ShapeKind>(), Ok(ShapeKind::Point));
+
+    // Enums without values are their own kind
+    let weekday: Weekday = "Friday".parse().unwrap();
+    assert_eq!(weekday.kind(), Weekday::Friday);
+    assert_eq!(weekday.name(), "Friday");
+
+    // Unknown alternatives have the catch-all kind
+    let drawing = catch_all::Drawing::from_dhall_str(r#"
+        { name = "sketch", shapes = [ < Point | Circle: { radius: Natural } | Square: { side: Natural } >.Square { side = 1 } ], layer = < Background | Foreground >.Background, status = < Draft | Published: Natural >.Draft }
+    "#).unwrap();
+    assert_eq!(drawing.shapes[0].kind(), catch_all::ShapeKind::Unknown);
+    assert_eq!(drawing.shapes[0].name(), "Unknown");
+    assert_eq!(catch_all::ShapeKind::COUNT, 2);
+    assert_eq!(drawing.layer.kind(), catch_all::DrawingAnon0::Background);
+    assert_eq!(drawing.layer.name(), "Background");
+}
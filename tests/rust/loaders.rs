@@ -0,0 +1,24 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/")]
+mod dhall { }
+
+fn main() {
+    let list = dhall::ShoppingList::from_dhall_file("../../../tests/dhall/shopping_list.dhall").unwrap();
+    assert_eq!(list.items.len(), 4);
+
+    let pair = dhall::Pair::<u64>::from_dhall_str("{ first = 1, second = 2 }").unwrap();
+    assert_eq!((pair.first, pair.second), (1, 2));
+
+    let person = dhall::Person::from_dhall_str(r#"
+        let AgeRange = < Baby | Toddler | Child | Teenager | Adult | Senior >
+        in { name = "Abby", age_range = AgeRange.Adult, occupation = Some { title = "Accountant", salary = "lots" } }
+    "#);
+    // The path of the mismatch comes first, followed by serde_dhall's own message
+    let error = person.unwrap_err().to_string();
+    assert_eq!(error.lines().next(), Some("occupation.salary: expected Natural, found Text"));
+    assert!(error.contains("<current file>:"));
+
+    let list = dhall::ShoppingList::from_dhall_str(r#"{ items = [ { item = "Milk", quantity = +1 }, { item = "Eggs", quantity = -12 } ] }"#);
+    assert_eq!(list.unwrap_err().to_string().lines().next(), Some("items[0].quantity: expected Natural, found Integer"));
+
+    assert!(dhall::ShoppingList::from_dhall_file_with_imports("../../../tests/dhall/shopping_list.dhall", false).is_err());
+}
@@ -0,0 +1,42 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/nested_generics/")]
+mod dhall { }
+
+const USES: &str = r#"
+let numbers = {
+    inner = { value = 1, count = 2 },
+    maybe = Some { value = 3, count = 4 },
+    many = [ { value = 5, count = 6 } ],
+    pairs = [ { first = 7, second = 8 } ],
+    deep = Some [ { item = 9 } ],
+    choice = < One: { item: Natural } | Other: { other: Optional Natural } | Neither >.One { item = 10 },
+}
+let texts = {
+    inner = { value = "a", count = 1 },
+    maybe = None { value: Text, count: Natural },
+    many = [] : List { value: Text, count: Natural },
+    pairs = [ { first = "b", second = "c" } ],
+    deep = None (List { item: Text }),
+    choice = < One: { item: Text } | Other: { other: Optional Text } | Neither >.Other { other = Some "d" },
+}
+in { numbers, texts }
+"#;
+
+fn main() {
+    let uses = dhall::Uses::from_dhall_str(USES).unwrap();
+    // The anonymous types nested in `Wrapper` take its type parameter, wherever they appear
+    let numbers: &dhall::Wrapper<u64> = &uses.numbers;
+    assert_eq!(numbers.inner.value + numbers.inner.count, 3);
+    assert_eq!(numbers.maybe.as_ref().map(|maybe| maybe.value), Some(3));
+    assert_eq!(numbers.many[0].value, 5);
+    assert_eq!(numbers.pairs[0].second, 8);
+    assert_eq!(numbers.deep.as_ref().map(|deep| deep[0].item), Some(9));
+    assert_eq!(numbers.choice.one().map(|one| one.item), Some(10));
+
+    let texts: &dhall::Wrapper<String> = &uses.texts;
+    assert_eq!(texts.inner.value, "a");
+    assert!(texts.maybe.is_none());
+    assert_eq!(texts.pairs[0].first, "b");
+    assert_eq!(texts.choice.other().and_then(|other| other.other.as_deref()), Some("d"));
+
+    assert_eq!(dhall::Uses::from_dhall_str(&uses.to_dhall_string().unwrap()).unwrap(), uses);
+}
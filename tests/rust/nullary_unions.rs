@@ -0,0 +1,52 @@
+use std::collections::BTreeSet;
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/")]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/catch_all/", catch_all_variants = true)]
+mod catch_all { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/nullary/")]
+mod nullary { }
+
+use dhall::{AmPm, Color};
+
+fn main() {
+    // Alternatives are ordered by name
+    assert_eq!(Color::ALL, [Color::Blue, Color::Green, Color::Red]);
+    assert_eq!(Color::COUNT, 3);
+    assert!(Color::Blue < Color::Red);
+    let colors = vec![Color::Red, Color::Blue, Color::Red].into_iter().collect::<BTreeSet<_>>();
+    assert_eq!(colors.into_iter().collect::<Vec<_>>(), vec![Color::Blue, Color::Red]);
+
+    // Whatever the order of the Dhall source, as Dhall unions are unordered
+    use nullary::Weekday;
+    assert_eq!(Weekday::ALL, [Weekday::Friday, Weekday::Monday, Weekday::Saturday, Weekday::Sunday, Weekday::Thursday, Weekday::Tuesday, Weekday::Wednesday]);
+    assert!(Weekday::Friday < Weekday::Monday && Weekday::Tuesday < Weekday::Wednesday);
+    let mut days = vec![Weekday::Sunday, Weekday::Monday, Weekday::Friday];
+    days.sort();
+    assert_eq!(days, [Weekday::Friday, Weekday::Monday, Weekday::Sunday]);
+    assert_eq!(Weekday::ALL.iter().max(), Some(&Weekday::Wednesday));
+
+    // Copy
+    let am_pm = AmPm::Pm;
+    let copy = am_pm;
+    assert_eq!(am_pm, copy);
+
+    // The Dhall alternative names
+    assert_eq!(AmPm::Am.as_str(), "Am");
+    assert_eq!(AmPm::Pm.to_string(), "Pm");
+    assert_eq!("Green".parse::<Color>(), Ok(Color::Green));
+    assert_eq!("green".parse::<Color>(), Err("'green' is not an alternative of Color, expected one of Blue, Green, Red".to_owned()));
+    for color in Color::ALL.iter().copied() {
+        assert_eq!(color.to_string().parse::<Color>(), Ok(color));
+    }
+
+    // The catch-all variant isn't a Dhall alternative
+    let drawing = catch_all::Drawing::from_dhall_str(r#"
+        { name = "sketch", shapes = [] : List ../../../tests/dhall/catch_all/shape.dhall, layer = < Background | Foreground | Overlay >.Overlay, status = < Draft | Published: Natural >.Draft }
+    "#).unwrap();
+    assert_eq!(drawing.layer.as_str(), "Unknown");
+    assert_eq!(catch_all::DrawingAnon0::COUNT, 2);
+    assert!("Unknown".parse::<catch_all::DrawingAnon0>().is_err());
+}
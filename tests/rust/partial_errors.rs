@@ -0,0 +1,10 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/invalid/")]
+mod dhall { }
+
+fn main() {
+    // `Valid` and `Team` are still generated, with their usual functions, even though `job.dhall`, `tool.dhall` and `value.dhall` are invalid.
+    // If they weren't, their uses below would add errors to the expected output
+    let valid = dhall::Valid::from_dhall_str(r#"{ id = 1, name = "valid" }"#).unwrap();
+    let team = dhall::Team { lead: valid.clone(), members: vec![valid] };
+    println!("{} {}", team.lead.name, team.to_dhall_string().unwrap());
+}
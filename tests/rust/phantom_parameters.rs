@@ -0,0 +1,16 @@
+use std::marker::PhantomData;
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/phantom/")]
+mod dhall { }
+
+fn main() {
+    let order = dhall::Order::from_dhall_str("{ customer = { id = 1 }, product = { id = 2 }, quantity = 3 }").unwrap();
+    let customer: &dhall::Id<dhall::User> = &order.customer;
+    let product: &dhall::Id<dhall::Product> = &order.product;
+    assert_eq!((customer.id, product.id), (1, 2));
+    assert_eq!(dhall::Order::from_dhall_str(&order.to_dhall_string().unwrap()).unwrap(), order);
+
+    // An unused type parameter is generic even without metadata, but can only be determined from Dhall with it
+    let tag: dhall::Tag<dhall::User> = dhall::Tag { label: String::from("vip"), _marker: PhantomData };
+    assert_eq!(tag.to_dhall_string().unwrap(), r#"{ label = "vip" } : { label : Text }"#);
+}
@@ -0,0 +1,43 @@
+use std::{rc::Rc, sync::Arc};
+
+use serde_dhall::StaticType;
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/pointers/", arc = "Settings", boxed = "Service.fallback", box_variants_over = 64)]
+mod dhall { }
+
+use dhall::{Service, Settings, Shape};
+
+fn main() {
+    let service = Service::from_dhall_str(r#"
+        let settings = { theme = "dark", limits = { users = 10, items = 100 } }
+        in {
+            name = "api",
+            settings = settings,
+            fallback = Some settings,
+            cache = { size = 64 },
+            shape = (../../../tests/dhall/pointers/shape.dhall).Polygon { label = "square", fill = "red", stroke = "black", points = [ { x = 0, y = 0 }, { x = 1, y = 1 } ] },
+        }
+    "#).unwrap();
+
+    // Every reference to `Settings` is shared, and the fallback field itself is boxed
+    let settings: &Arc<Settings> = &service.settings;
+    let fallback: &Box<Option<Arc<Settings>>> = &service.fallback;
+    assert_eq!(fallback.as_ref().as_ref(), Some(settings));
+    assert_eq!(service.cache.size, 64);
+    let _: &Rc<_> = &service.cache;
+
+    // Only the alternative estimated to be larger than 64 bytes is boxed
+    match &service.shape {
+        Shape::Polygon(polygon) => {
+            let polygon: &Box<_> = polygon;
+            assert_eq!(polygon.points.len(), 2);
+        },
+        _ => panic!("Expected a polygon"),
+    }
+    let circle = Shape::from_dhall_str("(../../../tests/dhall/pointers/shape.dhall).Circle { radius = 1 }").unwrap();
+    assert!(matches!(circle, Shape::Circle(circle) if circle.radius == 1));
+
+    // Pointers have the same Dhall type as the type they point to
+    assert_eq!(Service::static_type(), serde_dhall::from_file("../../../tests/dhall/pointers/service.dhall").parse::<serde_dhall::SimpleType>().unwrap());
+    assert_eq!(Service::from_dhall_str(&service.to_dhall_string().unwrap()).unwrap(), service);
+}
@@ -0,0 +1,28 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/recursive/")]
+mod dhall { }
+
+use dhall::{Chain, Expr, Forest, Tree};
+
+fn main() {
+    // Values of any depth can be loaded, even deeper than the Dhall type in the file
+    let tree = Tree::from_dhall_str("{ value = 1, children = [ { value = 2, children = [ { value = 3, children = [] : List <> } ] }, { value = 4, children = [] : List { value : Natural, children : List <> } } ] }").unwrap();
+    let children: &Vec<Tree> = &tree.children;
+    assert_eq!((children[0].children[0].value, children[1].value), (3, 4));
+    assert_eq!(Tree::from_dhall_str(&tree.to_dhall_string().unwrap()).unwrap(), tree);
+    assert_eq!(Tree::dhall_type_with_depth(1).to_string(), "{ children : List <  >, value : Natural }");
+
+    let chain = Chain { label: String::from("a"), next: Some(Box::new(Chain { label: String::from("b"), next: None })) };
+    assert_eq!(chain.to_dhall_string().unwrap(), r#"{ label = "a", next = Some { label = "b", next = None <  > } } : { label : Text, next : Optional { label : Text, next : Optional <  > } }"#);
+    assert_eq!(Chain::from_dhall_str(&chain.to_dhall_string().unwrap()).unwrap(), chain);
+
+    let expr = Expr::Negate(Box::new(Expr::Negate(Box::new(Expr::Literal(2)))));
+    assert_eq!(Expr::from_dhall_str(&expr.to_dhall_string().unwrap()).unwrap(), expr);
+    let expr = Expr::from_dhall_str("let Node = \\(E : Type) -> < Literal : Natural | Negate : E | Add : { left : E, right : E } > in (Node (Node (Node <>))).Add { left = (Node (Node <>)).Literal 1, right = (Node (Node <>)).Negate ((Node <>).Literal 2) }").unwrap();
+    let (left, right): (&Box<Expr>, &Box<Expr>) = match &expr { Expr::Add(add) => (&add.left, &add.right), _ => panic!("Expected Add") };
+    assert_eq!((left.as_ref(), right.as_ref()), (&Expr::Literal(1), &Expr::Negate(Box::new(Expr::Literal(2)))));
+    assert_eq!(Expr::from_dhall_str(&expr.to_dhall_string().unwrap()).unwrap(), expr);
+
+    // Types containing recursive types can be loaded, but have no Dhall type to be written with
+    let forest = Forest::from_dhall_str("{ name = \"grove\", trees = [] : List ../../../tests/dhall/recursive/tree.dhall }").unwrap();
+    assert!(forest.trees.is_empty());
+}
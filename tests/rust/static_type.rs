@@ -0,0 +1,18 @@
+use serde_dhall::{SimpleType, StaticType};
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/")]
+mod dhall { }
+
+fn dhall_type(expression: &str) -> SimpleType {
+    serde_dhall::from_str(expression).parse::<SimpleType>().unwrap()
+}
+
+fn main() {
+    assert_eq!(dhall::Person::static_type(), dhall_type("(../../../tests/dhall/schema/person.dhall).Type"));
+    assert_eq!(dhall::Pairs::static_type(), dhall_type("../../../tests/dhall/schema/pairs.dhall"));
+    assert_eq!(dhall::Pair::<u64>::static_type(), dhall_type("../../../tests/dhall/schema/pair.dhall Natural"));
+    assert_eq!(dhall::Quantity::<String>::static_type(), dhall_type("../../../tests/dhall/schema/quantity.dhall Text"));
+
+    let list = serde_dhall::from_file("../../../tests/dhall/shopping_list.dhall").static_type_annotation().parse::<dhall::ShoppingList>().unwrap();
+    assert!(!list.items.is_empty());
+}
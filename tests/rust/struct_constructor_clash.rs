@@ -0,0 +1,4 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/constructor_clash/", struct_constructors = true, named_struct_impl = true)]
+mod dhall { }
+
+fn main() {}
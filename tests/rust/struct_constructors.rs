@@ -0,0 +1,27 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/", struct_constructors = true)]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/phantom/", struct_constructors = true, field_visibility = "private")]
+mod phantom { }
+
+use dhall::{AmPm, Person, Time};
+
+fn main() {
+    // Fields are taken in alphabetical order
+    let time = Time::new(AmPm::Pm, 11, 30, 0);
+    assert_eq!(time, Time { am_pm: AmPm::Pm, hour: 11, minute: 30, second: 0 });
+    assert_eq!(time.with_hour(9).with_am_pm(AmPm::Am), Time { am_pm: AmPm::Am, hour: 9, minute: 30, second: 0 });
+
+    // Setters of optional fields take the value
+    let ada = Person::from_dhall_str(r#"
+        let Person = ../../../tests/dhall/schema/person.dhall
+        in Person::{ name = "Ada", age_range = < Baby | Toddler | Child | Teenager | Adult | Senior >.Adult, occupation = Some { title = "Mathematician", salary = 0 } }
+    "#).unwrap();
+    let occupation = ada.occupation.clone().unwrap();
+    let person = Person::new(ada.age_range, "Ada".to_owned(), None).with_occupation(occupation);
+    assert_eq!(person, ada);
+
+    // Types with phantom parameters and private fields
+    let id = phantom::Id::<phantom::User>::new(7).with_id(8);
+    assert_eq!(*id.id(), 8);
+}
@@ -0,0 +1,31 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/aliases/")]
+mod dhall { }
+
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/aliases/", alias_newtypes = true)]
+mod newtypes { }
+
+const ACCOUNT: &str = r#"{ id = 7, email = "a@example.com", backup_emails = [ "b@example.com" ], labels = [ "admin" ], scores = [ 1, 2 ] }"#;
+
+fn main() {
+    let _: dhall::UserId = 7u64;
+    let _: dhall::Tags = vec![String::from("tag")];
+    let _: dhall::Labels = vec![String::from("label")];
+    let scores: dhall::Items<u64> = vec![1, 2];
+
+    let account = dhall::Account::from_dhall_str(ACCOUNT).unwrap();
+    assert_eq!(account.email, "a@example.com");
+    assert_eq!(account.scores, scores);
+
+    // Only fields marked with metadata use the newtypes, a plain `Natural` is still `u64`
+    let account = newtypes::Account::from_dhall_str(ACCOUNT).unwrap();
+    let _: u64 = account.id;
+    assert_eq!(*account.email, "a@example.com");
+    assert_eq!(account.backup_emails, vec![newtypes::Email::from(String::from("b@example.com"))]);
+    assert_eq!(Vec::from(account.labels.clone()), vec![String::from("admin")]);
+    assert_eq!(account.scores.len(), 2);
+    assert_eq!(newtypes::Account::from_dhall_str(&account.to_dhall_string().unwrap()).unwrap(), account);
+
+    assert_eq!(newtypes::UserId::from(3).to_dhall_string().unwrap(), "3 : Natural");
+    assert_eq!(newtypes::UserId::from_dhall_str("3").unwrap(), newtypes::UserId::from(3));
+    assert!(newtypes::Tags::from_dhall_str("[ 1 ]").is_err());
+}
@@ -0,0 +1,59 @@
+use std::{net::IpAddr, time::Duration};
+
+use serde_dhall::StaticType;
+
+#[serde_dhall_typegen::dhall_types(
+    "../../../tests/dhall/type_map/",
+    type_map = "Duration = std::time::Duration",
+    type_map = "Port = u16",
+    type_map = "Server.address = std::net::IpAddr",
+    type_map = "Server.timeout_ms = std::time::Duration with crate::millis",
+    type_map = "Event.Timeout = std::time::Duration with crate::millis",
+)]
+mod dhall { }
+
+use dhall::{Event, Server};
+
+/// Reads and writes a `Duration` as a Natural number of milliseconds
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        (duration.as_millis() as u64).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+fn main() {
+    let server = Server::from_dhall_str(r#"
+        let Event = ../../../tests/dhall/type_map/event.dhall
+        in {
+            name = "api",
+            address = "127.0.0.1",
+            port = 8080,
+            timeout_ms = 1500,
+            uptime = { secs = 60, nanos = 0 },
+            history = [ { secs = 1, nanos = 500 } ],
+            events = [ Event.Started { secs = 2, nanos = 0 }, Event.Timeout 250, Event.Stopped ],
+        }
+    "#).unwrap();
+
+    // Mapped types and members use the existing Rust types, through their adapter if they have one
+    let address: &IpAddr = &server.address;
+    assert_eq!(address.to_string(), "127.0.0.1");
+    let port: u16 = server.port;
+    assert_eq!(port, 8080);
+    assert_eq!(server.timeout_ms, Duration::from_millis(1500));
+    assert_eq!(server.uptime, Duration::from_secs(60));
+    assert_eq!(server.history, vec![Duration::new(1, 500)]);
+    assert_eq!(server.events, vec![Event::Started(Duration::from_secs(2)), Event::Timeout(Duration::from_millis(250)), Event::Stopped]);
+
+    // The Dhall types are unchanged
+    assert_eq!(Server::static_type(), serde_dhall::from_file("../../../tests/dhall/type_map/server.dhall").parse::<serde_dhall::SimpleType>().unwrap());
+    assert_eq!(Server::from_dhall_str(&server.to_dhall_string().unwrap()).unwrap(), server);
+}
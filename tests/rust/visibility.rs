@@ -0,0 +1,20 @@
+mod config {
+    #[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/",
+        type_visibility = "pub(crate)",
+        field_visibility = "private",
+    )]
+    pub mod dhall { }
+}
+
+fn main() {
+    let persons = serde_dhall::from_file("../../../tests/dhall/persons.dhall").parse::<Vec<config::dhall::Person>>().unwrap();
+    for person in persons {
+        // Private fields are only accessible through the generated accessors
+        let occupation_title = if let Some(occupation) = person.occupation() {
+            occupation.title()
+        } else {
+            "Unemployed"
+        };
+        println!("{} ({:?}): {}", person.name(), person.age_range(), occupation_title);
+    }
+}
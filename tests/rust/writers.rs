@@ -0,0 +1,21 @@
+#[serde_dhall_typegen::dhall_types("../../../tests/dhall/schema/")]
+mod dhall { }
+
+fn main() {
+    let persons = serde_dhall::from_file("../../../tests/dhall/persons.dhall").parse::<Vec<dhall::Person>>().unwrap();
+    for person in persons {
+        let dhall = person.to_dhall_string().unwrap();
+        assert_eq!(dhall::Person::from_dhall_str(&dhall).unwrap(), person);
+    }
+
+    let list = dhall::ShoppingList { items: Vec::new() };
+    let dhall = list.to_dhall_string().unwrap();
+    assert!(dhall.contains("[] : List"));
+    assert_eq!(dhall::ShoppingList::from_dhall_str(&dhall).unwrap(), list);
+
+    let pair = dhall::Pair { first: String::from("a"), second: String::from("b") };
+    let path = std::env::temp_dir().join("serde_dhall_typegen_writers_pair.dhall");
+    pair.write_dhall_file(&path).unwrap();
+    assert_eq!(dhall::Pair::<String>::from_dhall_file(&path).unwrap(), pair);
+    std::fs::remove_file(&path).unwrap();
+}
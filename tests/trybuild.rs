@@ -1,15 +1,71 @@
+use std::{fs, io, path::Path};
+
+/// trybuild builds and runs each test from `target/tests/trybuild/<crate>/`, where the tests' `../../../tests/dhall/` paths
+/// point at `target/tests/dhall/`, so copy the Dhall files there
+fn copy_dhall_files(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dhall_files(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn trybuild() {
+    // CARGO_TARGET_TMPDIR is `<target dir>/tmp`
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).parent().unwrap();
+    let dhall_dir = target_dir.join("tests").join("dhall");
+    // Don't leave files removed from tests/dhall/ behind
+    if dhall_dir.exists() {
+        fs::remove_dir_all(&dhall_dir).unwrap();
+    }
+    copy_dhall_files(Path::new("tests/dhall"), &dhall_dir).unwrap();
+
     let t = trybuild::TestCases::new();
     t.pass("tests/rust/anonymous_types.rs");
+    t.pass("tests/rust/catch_all_variants.rs");
+    t.pass("tests/rust/conversions.rs");
+    t.pass("tests/rust/derived_types.rs");
+    t.pass("tests/rust/dhall_schema.rs");
+    t.pass("tests/rust/dhall_type.rs");
+    t.pass("tests/rust/doc_comments.rs");
+    t.pass("tests/rust/generic_union_conversions.rs");
+    t.pass("tests/rust/hand_written_renames.rs");
+    t.pass("tests/rust/hand_written_types.rs");
+    t.pass("tests/rust/inline_variant_records.rs");
+    t.pass("tests/rust/instantiation_aliases.rs");
+    t.pass("tests/rust/kind_enums.rs");
+    t.pass("tests/rust/loaders.rs");
     t.pass("tests/rust/metadata_lets.rs");
     t.pass("tests/rust/name_prefix.rs");
     t.pass("tests/rust/name_postfix.rs");
     t.pass("tests/rust/named_generics.rs");
+    t.pass("tests/rust/nested_generics.rs");
+    t.pass("tests/rust/nullary_unions.rs");
+    t.pass("tests/rust/phantom_parameters.rs");
+    t.pass("tests/rust/pointers.rs");
+    t.pass("tests/rust/recursive_types.rs");
+    t.pass("tests/rust/static_type.rs");
+    t.pass("tests/rust/struct_constructors.rs");
     t.pass("tests/rust/struct_impl.rs");
+    t.pass("tests/rust/type_aliases.rs");
     t.pass("tests/rust/type_impls.rs");
+    t.pass("tests/rust/type_map.rs");
     t.pass("tests/rust/type_parameters.rs");
+    t.pass("tests/rust/visibility.rs");
+    t.pass("tests/rust/writers.rs");
     
     // Negative tests disabled for now, anonymous type names are non-deterministic, and trybuild only allows exact matches for errors
     // t.compile_fail("tests/rust/no_impl.rs");
+    t.compile_fail("tests/rust/ambiguous_structure.rs");
+    t.compile_fail("tests/rust/dhall_errors.rs");
+    t.compile_fail("tests/rust/dhall_schema_errors.rs");
+    t.compile_fail("tests/rust/hand_written_mismatch.rs");
+    t.compile_fail("tests/rust/partial_errors.rs");
+    t.compile_fail("tests/rust/struct_constructor_clash.rs");
 }
\ No newline at end of file